@@ -0,0 +1,325 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{individual::Genome, scenario::Objective};
+
+// Bumped whenever the simulation or the fitness changes.
+const CACHE_VERSION: u32 = 2;
+
+/// Entries kept in memory and on disk; the oldest are evicted first.
+pub const MAX_CACHE_ENTRIES: usize = 200_000;
+
+// Exact gain bits, so a hit is the fitness the genome itself would get.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct CacheKey {
+    objective: u64,
+    seed: u64,
+    kp: u32,
+    ki: u32,
+    kd: u32,
+}
+
+impl CacheKey {
+    fn new(genome: Genome, objective: u64, seed: u64) -> Self {
+        Self {
+            objective,
+            seed,
+            kp: genome.kp.to_bits(),
+            ki: genome.ki.to_bits(),
+            kd: genome.kd.to_bits(),
+        }
+    }
+}
+
+// Fingerprints of the objectives seen so far, to serialise each only once.
+type Fingerprints = Arc<Mutex<Vec<(Arc<Objective>, u64)>>>;
+
+// On-disk layout, tagged with the cache and crate versions that wrote it.
+#[derive(Serialize, Deserialize)]
+struct StoredCache {
+    version: u32,
+    crate_version: String,
+    entries: Vec<(CacheKey, Option<f32>)>,
+}
+
+// Fitness by key, with the keys in insertion order for eviction.
+#[derive(Default)]
+struct Entries {
+    fitness: HashMap<CacheKey, f32>,
+    order: VecDeque<CacheKey>,
+}
+
+impl Entries {
+    // Stores `fitness` unless the key is known; returns the stored value.
+    fn insert(&mut self, key: CacheKey, fitness: f32) -> f32 {
+        if let Some(stored) = self.fitness.get(&key) {
+            return *stored;
+        }
+        let excess = (self.order.len() + 1).saturating_sub(MAX_CACHE_ENTRIES);
+        for oldest in self.order.drain(..excess) {
+            self.fitness.remove(&oldest);
+        }
+        self.fitness.insert(key, fitness);
+        self.order.push_back(key);
+        fitness
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct FitnessCache {
+    entries: Arc<Mutex<Entries>>,
+    hits: Arc<AtomicUsize>,
+    misses: Arc<AtomicUsize>,
+    screened: Arc<AtomicUsize>,
+    rejected: Arc<AtomicUsize>,
+    fingerprints: Fingerprints,
+    path: Option<PathBuf>,
+}
+
+impl FitnessCache {
+    /// Loads the cache at `path`, ignoring a file written by another version.
+    pub fn load(path: &str) -> io::Result<Self> {
+        let path = PathBuf::from(path);
+        let stored = if path.exists() {
+            serde_json::from_slice::<StoredCache>(&fs::read(&path)?)
+                .ok()
+                .filter(|stored| {
+                    stored.version == CACHE_VERSION
+                        && stored.crate_version == env!("CARGO_PKG_VERSION")
+                })
+        } else {
+            None
+        };
+        if stored.is_none() && path.exists() {
+            log::warn!(
+                "Ignoring fitness cache {} written by another version",
+                path.display()
+            );
+        }
+        let mut entries = Entries::default();
+        for (key, fitness) in stored.map(|stored| stored.entries).unwrap_or_default() {
+            entries.insert(key, fitness.unwrap_or(f32::NAN));
+        }
+
+        Ok(Self {
+            entries: Arc::new(Mutex::new(entries)),
+            path: Some(path),
+            ..Default::default()
+        })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let stored = StoredCache {
+            version: CACHE_VERSION,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            entries: {
+                let entries = self.entries.lock().unwrap();
+                entries
+                    .order
+                    .iter()
+                    .map(|key| (*key, Some(entries.fitness[key]).filter(|f| f.is_finite())))
+                    .collect()
+            },
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&stored)?)?;
+        fs::rename(tmp, Path::new(path))
+    }
+
+    /// Cached fitness of `genome`, or `eval` stored for it.
+    pub fn fitness(
+        &self,
+        genome: Genome,
+        objective: &Arc<Objective>,
+        seed: u64,
+        eval: impl FnOnce() -> f32,
+    ) -> f32 {
+        let key = CacheKey::new(genome, self.fingerprint(objective), seed);
+        if let Some(fitness) = self.entries.lock().unwrap().fitness.get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return *fitness;
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let fitness = eval();
+        self.entries.lock().unwrap().insert(key, fitness)
+    }
+
+    fn fingerprint(&self, objective: &Arc<Objective>) -> u64 {
+        let mut fingerprints = self.fingerprints.lock().unwrap();
+        if let Some((_, fingerprint)) = fingerprints
+            .iter()
+            .find(|(known, _)| Arc::ptr_eq(known, objective) || **known == **objective)
+        {
+            return *fingerprint;
+        }
+
+        let fingerprint = objective.fingerprint();
+        fingerprints.push((objective.clone(), fingerprint));
+        fingerprint
+    }
+
+    /// Counts a candidate screened out before simulation.
+    pub fn count_screened(&self, rejected: bool) {
+        self.screened.fetch_add(1, Ordering::Relaxed);
+        if rejected {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().unwrap().order.is_empty()
+    }
+
+    pub fn take_stats(&self) -> (usize, usize) {
+        (
+            self.hits.swap(0, Ordering::Relaxed),
+            self.misses.swap(0, Ordering::Relaxed),
+        )
+    }
+
+    /// Candidates screened and rejected since the last call.
+    pub fn take_screened(&self) -> (usize, usize) {
+        (
+            self.screened.swap(0, Ordering::Relaxed),
+            self.rejected.swap(0, Ordering::Relaxed),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join("pid_opt_tests");
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(format!("{name}-{}.json", std::process::id()))
+    }
+
+    fn key(i: u32) -> CacheKey {
+        CacheKey::new(Genome::new(i as f32, 0.0, 0.0), 0, 0)
+    }
+
+    #[test]
+    fn counts_hits_and_misses() {
+        let cache = FitnessCache::default();
+        let objective = Arc::new(Objective::default());
+        let genome = Genome::new(1.0, 2.0, 3.0);
+
+        assert_eq!(cache.fitness(genome, &objective, 1, || 0.5), 0.5);
+        assert_eq!(cache.fitness(genome, &objective, 1, || unreachable!()), 0.5);
+        assert_eq!(
+            cache.fitness(
+                genome,
+                &Arc::new(Objective::default()),
+                1,
+                || unreachable!()
+            ),
+            0.5
+        );
+        assert_eq!(cache.take_stats(), (2, 1));
+        assert_eq!(cache.take_stats(), (0, 0));
+
+        // Another seed or another gain bit pattern is another entry.
+        assert_eq!(cache.fitness(genome, &objective, 2, || 0.25), 0.25);
+        assert_eq!(
+            cache.fitness(Genome::new(-0.0, 2.0, 3.0), &objective, 1, || 0.1),
+            0.1
+        );
+        assert_eq!(
+            cache.fitness(Genome::new(0.0, 2.0, 3.0), &objective, 1, || 0.2),
+            0.2
+        );
+        assert_eq!(cache.take_stats(), (0, 3));
+        assert_eq!(cache.len(), 4);
+    }
+
+    #[test]
+    fn round_trips_through_its_file() {
+        let path = temp_path("cache");
+        let objective = Arc::new(Objective::default());
+        let cache = FitnessCache::load(path.to_str().unwrap()).unwrap();
+        assert!(cache.is_empty());
+        cache.fitness(Genome::new(1.0, 0.0, 0.0), &objective, 0, || 0.5);
+        cache.fitness(Genome::new(2.0, 0.0, 0.0), &objective, 0, || f32::INFINITY);
+        cache.save().unwrap();
+
+        let loaded = FitnessCache::load(path.to_str().unwrap()).unwrap();
+        assert_eq!(loaded.len(), 2);
+        let fitness =
+            |kp| loaded.fitness(Genome::new(kp, 0.0, 0.0), &objective, 0, || unreachable!());
+        assert_eq!(fitness(1.0), 0.5);
+        assert!(fitness(2.0).is_nan());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn ignores_a_file_of_another_version() {
+        let path = temp_path("stale-cache");
+        for (version, crate_version) in [
+            (CACHE_VERSION - 1, env!("CARGO_PKG_VERSION")),
+            (CACHE_VERSION, "0.0.0-other"),
+        ] {
+            let stored = StoredCache {
+                version,
+                crate_version: crate_version.to_string(),
+                entries: vec![(key(1), Some(0.5))],
+            };
+            fs::write(&path, serde_json::to_vec(&stored).unwrap()).unwrap();
+            assert!(
+                FitnessCache::load(path.to_str().unwrap())
+                    .unwrap()
+                    .is_empty()
+            );
+        }
+        fs::write(&path, "not json").unwrap();
+        assert!(
+            FitnessCache::load(path.to_str().unwrap())
+                .unwrap()
+                .is_empty()
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn evicts_the_oldest_entries() {
+        let mut entries = Entries::default();
+        for i in 0..=MAX_CACHE_ENTRIES as u32 {
+            entries.insert(key(i), i as f32);
+        }
+        assert_eq!(entries.order.len(), MAX_CACHE_ENTRIES);
+        assert_eq!(entries.fitness.len(), MAX_CACHE_ENTRIES);
+        assert!(!entries.fitness.contains_key(&key(0)));
+        assert!(entries.order.front() == Some(&key(1)));
+
+        // A known key keeps its first fitness and its place.
+        assert_eq!(entries.insert(key(1), -1.0), 1.0);
+        assert!(entries.order.front() == Some(&key(1)));
+    }
+}
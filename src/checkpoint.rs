@@ -0,0 +1,65 @@
+use std::{fs, io, path::Path};
+
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    genetic_algorithm::{DEFAULT_MUTATION_RATE, DEFAULT_REPLACE_RATE},
+    individual::Genome,
+    local_search::Memetic,
+    population::Seeding,
+    sampling::Sampling,
+    scenario::Objective,
+    stats::GenerationStats,
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub population_size: usize,
+    pub parallel_works: usize,
+    pub objective: Objective,
+    pub dir: String,
+    pub bounds: [f32; 3],
+    pub mutation_step: f32,
+    #[serde(default = "default_mutation_rate")]
+    pub mutation_rate: f32,
+    #[serde(default = "default_replace_rate")]
+    pub replace_rate: f32,
+    pub digit_range: (i32, i32),
+    pub seed: u64,
+    pub checkpoint_every: usize,
+    pub generation: usize,
+    pub individuals: Vec<(Genome, f32)>,
+    pub population_rng: ChaCha12Rng,
+    pub rng: ChaCha12Rng,
+    #[serde(default)]
+    pub history: Vec<GenerationStats>,
+    #[serde(default)]
+    pub memetic: Option<Memetic>,
+    #[serde(default)]
+    pub polished_at: Option<usize>,
+    #[serde(default)]
+    pub seeding: Option<Seeding>,
+    #[serde(default)]
+    pub sampling: Sampling,
+}
+
+fn default_mutation_rate() -> f32 {
+    DEFAULT_MUTATION_RATE
+}
+
+fn default_replace_rate() -> f32 {
+    DEFAULT_REPLACE_RATE
+}
+
+impl Checkpoint {
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(tmp, path)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+}
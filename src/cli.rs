@@ -0,0 +1,277 @@
+use clap::{Args, Parser, Subcommand};
+use log::LevelFilter;
+use serde::{Deserialize, de::value::StrDeserializer};
+
+use pid_opt::{
+    TraceOptions,
+    experiment::{Experiment, Language},
+    individual::{Genome, Individual, Model},
+    metric::Metric,
+    scenario::Objective,
+};
+
+use crate::{plot, run_dir::RunDir};
+
+#[derive(Parser)]
+#[command(version, about = "PID tuning by genetic algorithm")]
+pub struct Cli {
+    /// Verbosity of the log written to stderr and to each run's log.txt
+    #[arg(long, global = true, default_value = "info")]
+    pub log_level: LevelFilter,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the GA for one or more experiment files (default: experiments/*.toml)
+    Optimize {
+        configs: Vec<String>,
+        /// Reuse the latest run directory instead of creating a new one
+        #[arg(long, conflicts_with = "resume")]
+        overwrite: bool,
+        /// Continue the latest run from its checkpoint
+        #[arg(long)]
+        resume: bool,
+    },
+    /// Score a set of gains and print the metrics of every scenario
+    Evaluate {
+        #[command(flatten)]
+        objective: ObjectiveArgs,
+        #[command(flatten)]
+        gains: GainArgs,
+    },
+    /// Write the CSV traces of every scenario for a set of gains
+    Simulate {
+        #[command(flatten)]
+        objective: ObjectiveArgs,
+        #[command(flatten)]
+        gains: GainArgs,
+        /// Directory under output/ where the traces are written
+        #[arg(long, default_value = "simulate")]
+        output_dir: String,
+        /// Language of the plot labels
+        #[arg(long, value_parser = parse_variant::<Language>, default_value = "english")]
+        language: Language,
+        /// Also write the P, I and D terms, disturbance and noise
+        #[arg(long)]
+        detailed: bool,
+        /// Keep one trace sample out of every N
+        #[arg(long, default_value_t = 1, value_parser = parse_decimate)]
+        decimate: usize,
+    },
+    /// Score several sets of gains side by side
+    Compare {
+        #[command(flatten)]
+        objective: ObjectiveArgs,
+        /// Gains as kp,ki,kd (repeat for each set)
+        #[arg(long = "gains", required = true, value_parser = parse_genome)]
+        gains: Vec<Genome>,
+    },
+}
+
+#[derive(Args)]
+pub struct ObjectiveArgs {
+    /// Experiment file providing plant, scenarios, metric and seed
+    #[arg(long)]
+    config: Option<String>,
+    /// Plant model, overrides the experiment file
+    #[arg(long, value_parser = parse_variant::<Model>)]
+    plant: Option<Model>,
+    /// Error metric, overrides the experiment file
+    #[arg(long, value_parser = parse_variant::<Metric>)]
+    metric: Option<Metric>,
+    /// Seed of the random scenario, overrides the experiment file
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+#[derive(Args)]
+pub struct GainArgs {
+    /// Proportional gain
+    #[arg(long)]
+    kp: f32,
+    /// Integral gain
+    #[arg(long)]
+    ki: f32,
+    /// Derivative gain
+    #[arg(long)]
+    kd: f32,
+}
+
+impl GainArgs {
+    fn genome(&self) -> Genome {
+        Genome::new(self.kp, self.ki, self.kd)
+    }
+}
+
+fn parse_variant<T: for<'de> Deserialize<'de>>(value: &str) -> Result<T, String> {
+    T::deserialize(StrDeserializer::<serde::de::value::Error>::new(value))
+        .map_err(|err| err.to_string())
+}
+
+fn parse_decimate(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn parse_genome(value: &str) -> Result<Genome, String> {
+    let gains = value
+        .split(',')
+        .map(|gain| gain.trim().parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("invalid gain in `{value}`: {err}"))?;
+
+    match gains[..] {
+        [kp, ki, kd] => Ok(Genome::new(kp, ki, kd)),
+        _ => Err(format!("expected kp,ki,kd but got `{value}`")),
+    }
+}
+
+impl ObjectiveArgs {
+    fn resolve(&self) -> Result<(Objective, u64), String> {
+        let (mut objective, mut seed) = match &self.config {
+            Some(path) => {
+                let experiment = Experiment::load(path).map_err(|err| err.to_string())?;
+                (experiment.objective(), experiment.seed)
+            }
+            None => (Objective::default(), 0),
+        };
+
+        if let Some(plant) = self.plant {
+            objective.model = plant;
+        }
+        if let Some(metric) = self.metric {
+            objective.metric = metric;
+        }
+        if let Some(value) = self.seed {
+            seed = value;
+        }
+
+        Ok((objective, seed))
+    }
+}
+
+fn print_header(objective: &Objective, seed: u64) {
+    println!(
+        "Plant: {}, metric: {}, seed: {:#x}",
+        objective.model.name(),
+        objective.metric.name(),
+        seed
+    );
+}
+
+fn fitness(objective: &Objective, genome: Genome, seed: u64) -> f32 {
+    Individual::eval_fitness(genome.kp, genome.ki, genome.kd, false, objective, "", seed)
+}
+
+pub fn evaluate(objective: &ObjectiveArgs, gains: &GainArgs) -> Result<(), String> {
+    let (objective, seed) = objective.resolve()?;
+    let genome = gains.genome();
+    print_header(&objective, seed);
+    println!(
+        "PID = (kp: {:.10}, ki: {:.10}, kd: {:.10})",
+        genome.kp, genome.ki, genome.kd
+    );
+
+    print!("{:<12} {:>8}", "scenario", "weight");
+    for metric in Metric::ALL {
+        print!(" {:>14}", metric.name());
+    }
+    println!();
+
+    for scenario in Individual::eval_metrics(genome.kp, genome.ki, genome.kd, &objective, seed) {
+        print!("{:<12} {:>8.3}", scenario.name, scenario.weight);
+        for metric in Metric::ALL {
+            print!(" {:>14.6}", scenario.value(metric));
+        }
+        println!();
+    }
+
+    println!(
+        "Fitness ({}): {:.10}",
+        objective.metric.name(),
+        fitness(&objective, genome, seed)
+    );
+
+    Ok(())
+}
+
+pub fn simulate(
+    objective: &ObjectiveArgs,
+    gains: &GainArgs,
+    output_dir: &str,
+    language: Language,
+    options: &TraceOptions,
+) -> Result<(), String> {
+    let (objective, seed) = objective.resolve()?;
+    let genome = gains.genome();
+    let run = RunDir::prepare(output_dir, false, false).map_err(|err| err.to_string())?;
+
+    let fitness = Individual::write_traces(
+        genome.kp,
+        genome.ki,
+        genome.kd,
+        &objective,
+        run.name(),
+        seed,
+        options,
+    );
+    plot::plot_dir(&run.path(), language).map_err(|err| err.to_string())?;
+    run.write_manifest().map_err(|err| err.to_string())?;
+    print_header(&objective, seed);
+    println!(
+        "Traces written to {} (fitness {:.10})",
+        run.path().display(),
+        fitness
+    );
+
+    Ok(())
+}
+
+pub fn compare(objective: &ObjectiveArgs, gains: &[Genome]) -> Result<(), String> {
+    let (objective, seed) = objective.resolve()?;
+    print_header(&objective, seed);
+
+    let scored = gains
+        .iter()
+        .map(|genome| {
+            let metrics =
+                Individual::eval_metrics(genome.kp, genome.ki, genome.kd, &objective, seed);
+            (genome, metrics, fitness(&objective, *genome, seed))
+        })
+        .collect::<Vec<_>>();
+
+    print!("{:>12} {:>12} {:>12}", "kp", "ki", "kd");
+    for scenario in &objective.scenarios {
+        print!(" {:>12}", scenario.name());
+    }
+    println!(" {:>14}", "fitness");
+
+    for (genome, metrics, fitness) in &scored {
+        print!(
+            "{:>12.6} {:>12.6} {:>12.6}",
+            genome.kp, genome.ki, genome.kd
+        );
+        for scenario in metrics {
+            print!(" {:>12.6}", scenario.value(objective.metric));
+        }
+        println!(" {:>14.10}", fitness);
+    }
+
+    let best = scored
+        .iter()
+        .filter(|(_, _, fitness)| fitness.is_finite())
+        .min_by(|a, b| a.2.total_cmp(&b.2));
+    if let Some((genome, _, fitness)) = best {
+        println!(
+            "Best: PID = (kp: {:.10}, ki: {:.10}, kd: {:.10}) with fitness {:.10}",
+            genome.kp, genome.ki, genome.kd, fitness
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,611 @@
+use std::{sync::Arc, time::Instant};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::{
+    cache::FitnessCache,
+    individual::{Genome, Individual, gaussian},
+    observer::{Observer, Observers},
+    optimizer::{Optimizer, OptimizerError, check_bounds},
+    population::Population,
+    scenario::Objective,
+    stats::GenerationStats,
+};
+
+/// Initial step size used when the builder sets none, relative to the box.
+pub const DEFAULT_INITIAL_STEP: f32 = 0.3;
+
+type Vector = [f64; 3];
+type Matrix = [[f64; 3]; 3];
+
+fn identity() -> Matrix {
+    [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+}
+
+// Eigen decomposition of the leading `n` x `n` block by Jacobi rotations.
+fn eigen(matrix: &Matrix, n: usize) -> (Vector, Matrix) {
+    let mut a = *matrix;
+    let mut v = identity();
+    for _ in 0..50 {
+        let off = (0..n)
+            .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j] * a[i][j])
+            .sum::<f64>();
+        if off < 1e-30 {
+            break;
+        }
+
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q] == 0.0 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+
+                for row in a.iter_mut().take(n) {
+                    let (akp, akq) = (row[p], row[q]);
+                    row[p] = c * akp - s * akq;
+                    row[q] = s * akp + c * akq;
+                }
+                let (ap, aq) = (a[p], a[q]);
+                a[p] = std::array::from_fn(|k| c * ap[k] - s * aq[k]);
+                a[q] = std::array::from_fn(|k| s * ap[k] + c * aq[k]);
+                for row in v.iter_mut().take(n) {
+                    let (vkp, vkq) = (row[p], row[q]);
+                    row[p] = c * vkp - s * vkq;
+                    row[q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    ([a[0][0], a[1][1], a[2][2]], v)
+}
+
+// Mirrors a coordinate into [0, 1], folding again samples that land far out.
+fn reflect(x: f64) -> f64 {
+    let x = x.rem_euclid(2.0);
+    if x > 1.0 { 2.0 - x } else { x }
+}
+
+fn rank(fitness: f32) -> f32 {
+    if fitness.is_finite() {
+        fitness
+    } else {
+        f32::INFINITY
+    }
+}
+
+// Learning rates of Hansen's tutorial.
+struct Constants {
+    weights: Vec<f64>,
+    mueff: f64,
+    cc: f64,
+    cs: f64,
+    c1: f64,
+    cmu: f64,
+    damps: f64,
+    chi_n: f64,
+}
+
+impl Constants {
+    fn new(n: usize, lambda: usize) -> Self {
+        let mu = (lambda / 2).max(1);
+        let raw = (1..=mu)
+            .map(|i| ((lambda as f64 + 1.0) / 2.0).ln() - (i as f64).ln())
+            .collect::<Vec<_>>();
+        let sum = raw.iter().sum::<f64>();
+        let weights = raw.iter().map(|w| w / sum).collect::<Vec<_>>();
+        let mueff = 1.0 / weights.iter().map(|w| w * w).sum::<f64>();
+
+        let n = n as f64;
+        let c1 = 2.0 / ((n + 1.3).powi(2) + mueff);
+        let cs = (mueff + 2.0) / (n + mueff + 5.0);
+        Self {
+            cc: (4.0 + mueff / n) / (n + 4.0 + 2.0 * mueff / n),
+            cs,
+            c1,
+            cmu: (1.0 - c1).min(2.0 * (mueff - 2.0 + 1.0 / mueff) / ((n + 2.0).powi(2) + mueff)),
+            damps: 1.0 + 2.0 * (((mueff - 1.0) / (n + 1.0)).sqrt() - 1.0).max(0.0) + cs,
+            chi_n: n.sqrt() * (1.0 - 1.0 / (4.0 * n) + 1.0 / (21.0 * n * n)),
+            weights,
+            mueff,
+        }
+    }
+}
+
+/// Covariance matrix adaptation evolution strategy over PID gains.
+pub struct CmaEs {
+    mean: Vector,
+    step_size: f64,
+    covariance: Matrix,
+    basis: Matrix,
+    scales: Vector,
+    path_c: Vector,
+    path_s: Vector,
+    constants: Constants,
+    offspring: Vec<Individual>,
+    best: Option<Individual>,
+    generation: usize,
+    start_generation: usize,
+    lambda: usize,
+    active: Vec<usize>,
+    upper: [f32; 3],
+    initial_step: f32,
+    start: Option<Genome>,
+    parallel_works: usize,
+    objective: Arc<Objective>,
+    dir: Arc<str>,
+    seed: u64,
+    rng: ChaCha12Rng,
+    cache: FitnessCache,
+    history: Vec<GenerationStats>,
+    observers: Observers,
+}
+
+/// Configures and builds a [`CmaEs`], evaluating its starting mean.
+#[derive(Default)]
+pub struct CmaEsBuilder {
+    population_size: Option<usize>,
+    parallel_works: usize,
+    objective: Objective,
+    dir: Arc<str>,
+    max_kp: Option<f32>,
+    max_ki: Option<f32>,
+    max_kd: Option<f32>,
+    initial_step: Option<f32>,
+    start: Option<Genome>,
+    start_generation: usize,
+    seed: u64,
+    cache: FitnessCache,
+    observers: Observers,
+}
+
+// Required builder parameters once checked.
+struct Settings {
+    upper: [f32; 3],
+    initial_step: f32,
+    active: Vec<usize>,
+    lambda: usize,
+}
+
+impl CmaEsBuilder {
+    /// Offspring sampled per generation.
+    pub fn with_population_size(mut self, size: usize) -> Self {
+        self.population_size = Some(size);
+        self
+    }
+
+    /// Worker threads; 0 evaluates on the calling thread.
+    pub fn with_parallel_works(mut self, works: usize) -> Self {
+        self.parallel_works = works;
+        self
+    }
+
+    /// Plant, metric and scenarios offspring are scored against.
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    /// Directory under `output/` where traces of the best individual are written.
+    pub fn with_output_dir(mut self, dir: impl Into<Arc<str>>) -> Self {
+        self.dir = dir.into();
+        self
+    }
+
+    /// Upper bound of kp.
+    pub fn with_max_kp(mut self, max_kp: f32) -> Self {
+        self.max_kp = Some(max_kp);
+        self
+    }
+
+    /// Upper bound of ki.
+    pub fn with_max_ki(mut self, max_ki: f32) -> Self {
+        self.max_ki = Some(max_ki);
+        self
+    }
+
+    /// Upper bound of kd.
+    pub fn with_max_kd(mut self, max_kd: f32) -> Self {
+        self.max_kd = Some(max_kd);
+        self
+    }
+
+    /// Initial step size as a fraction of the width of the search box.
+    pub fn with_initial_step(mut self, step: f32) -> Self {
+        self.initial_step = Some(step);
+        self
+    }
+
+    /// Starts the mean at `genome`, widening the bounds to contain it.
+    pub fn with_start(mut self, genome: Genome) -> Self {
+        self.start = Some(genome);
+        self
+    }
+
+    /// Numbers generations from `generation`.
+    pub fn with_start_generation(mut self, generation: usize) -> Self {
+        self.start_generation = generation;
+        self
+    }
+
+    /// Seed of every random stream.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Cache consulted before simulating a genome.
+    pub fn with_fitness_cache(mut self, cache: FitnessCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Adds an observer notified of every generation; see [`Observer`].
+    pub fn with_observer(mut self, observer: impl Observer + 'static) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    /// Checks the settings without evaluating anything.
+    pub fn validate(&self) -> Result<(), OptimizerError> {
+        self.settings().map(|_| ())
+    }
+
+    fn settings(&self) -> Result<Settings, OptimizerError> {
+        let mut upper = [
+            self.max_kp.ok_or(OptimizerError::Missing("max_kp"))?,
+            self.max_ki.ok_or(OptimizerError::Missing("max_ki"))?,
+            self.max_kd.ok_or(OptimizerError::Missing("max_kd"))?,
+        ];
+        check_bounds(upper)?;
+        if let Some(start) = self.start {
+            for (bound, gain) in upper.iter_mut().zip([start.kp, start.ki, start.kd]) {
+                if !(gain.is_finite() && gain >= 0.0) {
+                    return Err(OptimizerError::Invalid(
+                        "start",
+                        format!("gains must be non-negative numbers, got {gain}"),
+                    ));
+                }
+                *bound = bound.max(gain);
+            }
+        }
+
+        let initial_step = self.initial_step.unwrap_or(DEFAULT_INITIAL_STEP);
+        if !(initial_step.is_finite() && initial_step > 0.0) {
+            return Err(OptimizerError::Invalid(
+                "initial_step",
+                format!("must be a positive number, got {initial_step}"),
+            ));
+        }
+
+        let active = (0..3).filter(|&d| upper[d] > 0.0).collect::<Vec<_>>();
+        let lambda = self
+            .population_size
+            .unwrap_or(4 + (3.0 * (active.len() as f64).ln()) as usize);
+        if lambda < 2 {
+            return Err(OptimizerError::Invalid(
+                "population_size",
+                format!("must be at least 2, got {lambda}"),
+            ));
+        }
+
+        self.objective
+            .validate()
+            .map_err(OptimizerError::Objective)?;
+
+        Ok(Settings {
+            upper,
+            initial_step,
+            active,
+            lambda,
+        })
+    }
+
+    /// Evaluates the starting mean and a first set of samples.
+    pub fn build(self) -> Result<CmaEs, OptimizerError> {
+        let Settings {
+            upper,
+            initial_step,
+            active,
+            lambda,
+        } = self.settings()?;
+        let n = active.len();
+
+        let mut cma = CmaEs {
+            mean: [0.0; 3],
+            step_size: initial_step as f64,
+            covariance: identity(),
+            basis: identity(),
+            scales: [1.0; 3],
+            path_c: [0.0; 3],
+            path_s: [0.0; 3],
+            constants: Constants::new(n, lambda),
+            offspring: vec![],
+            best: None,
+            generation: self.start_generation,
+            start_generation: self.start_generation,
+            lambda,
+            active,
+            upper,
+            initial_step,
+            start: self.start,
+            parallel_works: self.parallel_works,
+            objective: Arc::new(self.objective),
+            dir: self.dir.clone(),
+            seed: self.seed,
+            rng: ChaCha12Rng::seed_from_u64(self.seed),
+            cache: self.cache,
+            history: vec![],
+            observers: self.observers,
+        };
+        cma.initialise()?;
+
+        Ok(cma)
+    }
+}
+
+impl CmaEs {
+    fn genome(&self, x: &Vector) -> Genome {
+        let mut gains = [0.0; 3];
+        for (k, &d) in self.active.iter().enumerate() {
+            gains[d] = (x[k] * self.upper[d] as f64) as f32;
+        }
+
+        Genome::new(gains[0], gains[1], gains[2])
+    }
+
+    fn evaluate(&self, samples: &[Vector]) -> Vec<Individual> {
+        Population::evaluate(
+            samples.iter().map(|x| self.genome(x)).collect(),
+            self.parallel_works,
+            self.objective.clone(),
+            self.dir.clone(),
+            self.seed,
+            self.cache.clone(),
+        )
+    }
+
+    // Keeps the best individual ever evaluated; the offspring are not elitist.
+    fn keep_best(&mut self, individuals: &[Individual]) {
+        for ind in individuals {
+            let fitness = ind.fitness();
+            if fitness.is_finite()
+                && self
+                    .best
+                    .as_ref()
+                    .is_none_or(|best| fitness < best.fitness())
+            {
+                self.best = Some(ind.clone());
+            }
+        }
+    }
+
+    // Moves the mean to the best half of `samples` and adapts the distribution.
+    fn update(&mut self, samples: &[Vector], order: &[usize]) {
+        let n = self.active.len();
+        let k = &self.constants;
+        let old = self.mean;
+        let sigma = self.step_size;
+
+        let steps = order
+            .iter()
+            .zip(&k.weights)
+            .map(|(&i, &w)| {
+                (
+                    w,
+                    std::array::from_fn::<f64, 3, _>(|d| (samples[i][d] - old[d]) / sigma),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut y_w = [0.0; 3];
+        for (w, y) in &steps {
+            for (sum, y) in y_w.iter_mut().zip(y) {
+                *sum += w * y;
+            }
+        }
+        self.mean = std::array::from_fn(|d| old[d] + sigma * y_w[d]);
+
+        // C^-1/2 y_w = B D^-1 B^T y_w
+        let whitened: Vector = std::array::from_fn(|j| {
+            (0..n).map(|i| self.basis[i][j] * y_w[i]).sum::<f64>() / self.scales[j]
+        });
+        let whitened: Vector =
+            std::array::from_fn(|i| (0..n).map(|j| self.basis[i][j] * whitened[j]).sum());
+
+        let cs = k.cs;
+        for (path, w) in self.path_s.iter_mut().zip(whitened) {
+            *path = (1.0 - cs) * *path + (cs * (2.0 - cs) * k.mueff).sqrt() * w;
+        }
+        let norm_s = self.path_s[..n].iter().map(|p| p * p).sum::<f64>().sqrt();
+        let evaluated = (self.generation - self.start_generation + 1) as i32;
+        let hsig = norm_s / (1.0 - (1.0 - cs).powi(2 * evaluated)).sqrt() / k.chi_n
+            < 1.4 + 2.0 / (n as f64 + 1.0);
+
+        let cc = k.cc;
+        for (path, y) in self.path_c.iter_mut().zip(y_w) {
+            *path *= 1.0 - cc;
+            if hsig {
+                *path += (cc * (2.0 - cc) * k.mueff).sqrt() * y;
+            }
+        }
+
+        let delta = if hsig { 0.0 } else { cc * (2.0 - cc) };
+        for i in 0..n {
+            for j in 0..n {
+                let rank_mu = steps.iter().map(|(w, y)| w * y[i] * y[j]).sum::<f64>();
+                self.covariance[i][j] = (1.0 - k.c1 - k.cmu) * self.covariance[i][j]
+                    + k.c1 * (self.path_c[i] * self.path_c[j] + delta * self.covariance[i][j])
+                    + k.cmu * rank_mu;
+            }
+        }
+
+        // Steps wider than the box are pointless, so the step size is capped.
+        self.step_size = (sigma * ((cs / k.damps) * (norm_s / k.chi_n - 1.0)).exp()).min(1.0);
+
+        let (values, basis) = eigen(&self.covariance, n);
+        self.basis = basis;
+        self.scales = values.map(|value| value.max(1e-20).sqrt());
+    }
+
+    // Draws `lambda` points, reflected into the unit box.
+    fn sample(&mut self) -> Vec<Vector> {
+        let n = self.active.len();
+        let mut samples = Vec::with_capacity(self.lambda);
+        for _ in 0..self.lambda {
+            let z: Vector = std::array::from_fn(|d| {
+                if d < n {
+                    gaussian(&mut self.rng) as f64
+                } else {
+                    0.0
+                }
+            });
+            let x: Vector = std::array::from_fn(|i| {
+                if i < n {
+                    let y = (0..n)
+                        .map(|j| self.basis[i][j] * self.scales[j] * z[j])
+                        .sum::<f64>();
+                    reflect(self.mean[i] + self.step_size * y)
+                } else {
+                    0.0
+                }
+            });
+            samples.push(x);
+        }
+
+        samples
+    }
+
+    // Appends the statistics of the offspring, with `best` the best so far.
+    fn record(&mut self, evaluations: usize, filtered: usize, start: Instant) {
+        let population = Population::from_individuals(self.offspring.clone(), self.seed);
+        let stats = GenerationStats::new(self.generation, &population)
+            .unwrap_or_else(|| GenerationStats::empty(self.generation, self.best.as_ref()));
+        let mut stats = stats.with_evaluations(evaluations, filtered, &self.cache, start);
+        if let Some(best) = &self.best {
+            stats.best = best.fitness();
+            stats.best_kp = best.kp();
+            stats.best_ki = best.ki();
+            stats.best_kd = best.kd();
+        }
+        self.history.push(stats);
+    }
+
+    /// Current step size as a fraction of the width of the search box.
+    pub fn step_size(&self) -> f32 {
+        self.step_size as f32
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl Optimizer for CmaEs {
+    fn initialise(&mut self) -> Result<(), OptimizerError> {
+        let start = Instant::now();
+        let n = self.active.len();
+        self.rng = ChaCha12Rng::seed_from_u64(self.seed);
+        self.mean = [0.0; 3];
+        for (k, &d) in self.active.iter().enumerate() {
+            self.mean[k] = match self.start {
+                Some(genome) => ([genome.kp, genome.ki, genome.kd][d] / self.upper[d]) as f64,
+                None => self.rng.random::<f64>(),
+            };
+        }
+        self.step_size = self.initial_step as f64;
+        self.covariance = identity();
+        self.basis = identity();
+        self.scales = [1.0; 3];
+        self.path_c = [0.0; 3];
+        self.path_s = [0.0; 3];
+        self.generation = self.start_generation;
+        self.history.clear();
+        self.best = None;
+
+        // Samples too, so an unstable mean does not leave the run without a best.
+        let mut samples = vec![self.mean];
+        samples.extend(self.sample());
+        self.offspring = self.evaluate(&samples);
+        let offspring = self.offspring.clone();
+        self.keep_best(&offspring);
+        let filtered = offspring
+            .iter()
+            .filter(|ind| !ind.fitness().is_finite())
+            .count();
+        log::debug!("CMA-ES over {n} gains with {} offspring", self.lambda);
+        self.record(samples.len(), filtered, start);
+
+        Ok(())
+    }
+
+    fn step(&mut self) -> Result<Individual, OptimizerError> {
+        let start = Instant::now();
+        let previous_best = self.best.as_ref().map(|best| best.fitness());
+        self.observers.generation_start(self.generation + 1);
+
+        let samples = self.sample();
+        let offspring = self.evaluate(&samples);
+        let filtered = offspring
+            .iter()
+            .filter(|ind| !ind.fitness().is_finite())
+            .count();
+        let mut order = (0..samples.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| {
+            rank(offspring[a].fitness()).total_cmp(&rank(offspring[b].fitness()))
+        });
+
+        // Without any finite offspring the distribution is kept.
+        if filtered < offspring.len() {
+            self.update(&samples, &order);
+        }
+        self.keep_best(&offspring);
+        // The least bad offspring stands in until a finite fitness is found.
+        let best = self
+            .best
+            .clone()
+            .or_else(|| order.first().map(|&i| offspring[i].clone()))
+            .ok_or(OptimizerError::EmptyPopulation)?;
+        self.offspring = offspring;
+
+        self.generation += 1;
+        self.record(samples.len(), filtered, start);
+
+        if let Some(stats) = self.history.last() {
+            let improved = previous_best.is_none_or(|fitness| best.fitness() < fitness);
+            self.observers.generation_end(stats, &best, improved);
+        }
+
+        Ok(best)
+    }
+
+    fn best(&self) -> Option<Individual> {
+        self.best.clone()
+    }
+
+    fn statistics(&self) -> &[GenerationStats] {
+        &self.history
+    }
+
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Offspring of the last generation with a finite fitness, best first.
+    fn genomes(&self) -> Vec<Genome> {
+        Population::from_individuals(self.offspring.clone(), self.seed)
+            .individuals()
+            .iter()
+            .map(|ind| ind.genome())
+            .collect()
+    }
+
+    fn observers(&mut self) -> &mut Observers {
+        &mut self.observers
+    }
+}
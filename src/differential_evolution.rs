@@ -0,0 +1,454 @@
+use std::{sync::Arc, time::Instant};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cache::FitnessCache,
+    individual::{Genome, Individual},
+    observer::{Observer, Observers},
+    optimizer::{Optimizer, OptimizerError, check_bounds, check_rate},
+    population::{MAX_POPULATION_SIZE, Population},
+    scenario::Objective,
+    stats::GenerationStats,
+};
+
+/// Differential weight F used when the builder sets none.
+pub const DEFAULT_DIFFERENTIAL_WEIGHT: f32 = 0.5;
+/// Crossover rate CR used when the builder sets none.
+pub const DEFAULT_CROSSOVER_RATE: f32 = 0.9;
+
+/// How the mutant vector of each target is built.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Strategy {
+    /// `x_r1 + F (x_r2 - x_r3)`.
+    #[default]
+    #[serde(rename = "rand/1/bin")]
+    Rand1Bin,
+    /// `x_best + F (x_r1 - x_r2)`.
+    #[serde(rename = "best/1/bin")]
+    Best1Bin,
+    /// `x_i + F (x_best - x_i) + F (x_r1 - x_r2)`.
+    #[serde(rename = "current-to-best/1")]
+    CurrentToBest1,
+}
+
+impl Strategy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Strategy::Rand1Bin => "rand/1/bin",
+            Strategy::Best1Bin => "best/1/bin",
+            Strategy::CurrentToBest1 => "current-to-best/1",
+        }
+    }
+}
+
+fn gains(genome: Genome) -> [f32; 3] {
+    [genome.kp, genome.ki, genome.kd]
+}
+
+// Random member index not in `taken`.
+fn pick(rng: &mut impl Rng, n: usize, taken: &[usize]) -> usize {
+    loop {
+        let index = rng.random_range(0..n);
+        if !taken.contains(&index) {
+            return index;
+        }
+    }
+}
+
+/// Differential evolution over PID gains, with trials clamped to `[0, max_k*]`.
+pub struct DifferentialEvolution {
+    population: Population,
+    generation: usize,
+    population_size: usize,
+    parallel_works: usize,
+    objective: Arc<Objective>,
+    dir: Arc<str>,
+    bounds: [f32; 3],
+    strategy: Strategy,
+    differential_weight: f32,
+    crossover_rate: f32,
+    seed: u64,
+    rng: ChaCha12Rng,
+    cache: FitnessCache,
+    history: Vec<GenerationStats>,
+    observers: Observers,
+}
+
+/// Configures and builds a [`DifferentialEvolution`], evaluating its initial population.
+#[derive(Default)]
+pub struct DifferentialEvolutionBuilder {
+    population_size: Option<usize>,
+    parallel_works: usize,
+    objective: Objective,
+    dir: Arc<str>,
+    max_kp: Option<f32>,
+    max_ki: Option<f32>,
+    max_kd: Option<f32>,
+    strategy: Strategy,
+    differential_weight: Option<f32>,
+    crossover_rate: Option<f32>,
+    seed: u64,
+    cache: FitnessCache,
+    observers: Observers,
+}
+
+// Required builder parameters once checked.
+struct Settings {
+    population_size: usize,
+    bounds: [f32; 3],
+    differential_weight: f32,
+    crossover_rate: f32,
+}
+
+impl DifferentialEvolutionBuilder {
+    /// Number of individuals in the population.
+    pub fn with_population_size(mut self, size: usize) -> Self {
+        self.population_size = Some(size);
+        self
+    }
+
+    /// Worker threads; 0 evaluates on the calling thread.
+    pub fn with_parallel_works(mut self, works: usize) -> Self {
+        self.parallel_works = works;
+        self
+    }
+
+    /// Plant, metric and scenarios individuals are scored against.
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    /// Directory under `output/` where traces of the best individual are written.
+    pub fn with_output_dir(mut self, dir: impl Into<Arc<str>>) -> Self {
+        self.dir = dir.into();
+        self
+    }
+
+    /// Upper bound of kp in the initial population.
+    pub fn with_max_kp(mut self, max_kp: f32) -> Self {
+        self.max_kp = Some(max_kp);
+        self
+    }
+
+    /// Upper bound of ki in the initial population.
+    pub fn with_max_ki(mut self, max_ki: f32) -> Self {
+        self.max_ki = Some(max_ki);
+        self
+    }
+
+    /// Upper bound of kd in the initial population.
+    pub fn with_max_kd(mut self, max_kd: f32) -> Self {
+        self.max_kd = Some(max_kd);
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Scale F of the difference vectors, in `(0, 2]`.
+    pub fn with_differential_weight(mut self, weight: f32) -> Self {
+        self.differential_weight = Some(weight);
+        self
+    }
+
+    /// Chance CR of taking each gain from the mutant rather than the target.
+    pub fn with_crossover_rate(mut self, rate: f32) -> Self {
+        self.crossover_rate = Some(rate);
+        self
+    }
+
+    /// Seed of every random stream.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Cache consulted before simulating a genome.
+    pub fn with_fitness_cache(mut self, cache: FitnessCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Adds an observer notified of every generation; see [`Observer`].
+    pub fn with_observer(mut self, observer: impl Observer + 'static) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    /// Checks the settings without evaluating anything.
+    pub fn validate(&self) -> Result<(), OptimizerError> {
+        self.settings().map(|_| ())
+    }
+
+    fn settings(&self) -> Result<Settings, OptimizerError> {
+        let population_size = self
+            .population_size
+            .ok_or(OptimizerError::Missing("population_size"))?;
+        let bounds = [
+            self.max_kp.ok_or(OptimizerError::Missing("max_kp"))?,
+            self.max_ki.ok_or(OptimizerError::Missing("max_ki"))?,
+            self.max_kd.ok_or(OptimizerError::Missing("max_kd"))?,
+        ];
+        let differential_weight = self
+            .differential_weight
+            .unwrap_or(DEFAULT_DIFFERENTIAL_WEIGHT);
+        let crossover_rate = self.crossover_rate.unwrap_or(DEFAULT_CROSSOVER_RATE);
+
+        if !(4..=MAX_POPULATION_SIZE).contains(&population_size) {
+            return Err(OptimizerError::Invalid(
+                "population_size",
+                format!("must be between 4 and {MAX_POPULATION_SIZE}, got {population_size}"),
+            ));
+        }
+        if !(differential_weight > 0.0 && differential_weight <= 2.0) {
+            return Err(OptimizerError::Invalid(
+                "differential_weight",
+                format!("must be in (0, 2], got {differential_weight}"),
+            ));
+        }
+        check_rate("crossover_rate", crossover_rate)?;
+        check_bounds(bounds)?;
+
+        self.objective
+            .validate()
+            .map_err(OptimizerError::Objective)?;
+
+        Ok(Settings {
+            population_size,
+            bounds,
+            differential_weight,
+            crossover_rate,
+        })
+    }
+
+    /// Evaluates the initial population and returns the DE at generation 0.
+    pub fn build(self) -> Result<DifferentialEvolution, OptimizerError> {
+        let Settings {
+            population_size,
+            bounds,
+            differential_weight,
+            crossover_rate,
+        } = self.settings()?;
+
+        let mut de = DifferentialEvolution {
+            population: Population::from_parts(vec![], ChaCha12Rng::seed_from_u64(self.seed)),
+            generation: 0,
+            population_size,
+            parallel_works: self.parallel_works,
+            objective: Arc::new(self.objective),
+            dir: self.dir.clone(),
+            bounds,
+            strategy: self.strategy,
+            differential_weight,
+            crossover_rate,
+            seed: self.seed,
+            rng: ChaCha12Rng::seed_from_u64(self.seed),
+            cache: self.cache,
+            history: vec![],
+            observers: self.observers,
+        };
+        de.initialise()?;
+
+        Ok(de)
+    }
+}
+
+impl DifferentialEvolution {
+    // Mutant of member `i` followed by binomial crossover with it.
+    fn trial(&mut self, i: usize) -> Genome {
+        let individuals = self.population.individuals();
+        let n = individuals.len();
+        let member = |index: usize| gains(individuals[index].genome());
+        let f = self.differential_weight;
+
+        let r1 = pick(&mut self.rng, n, &[i]);
+        let r2 = pick(&mut self.rng, n, &[i, r1]);
+        let target = member(i);
+        let mutant = match self.strategy {
+            Strategy::Rand1Bin => {
+                let r3 = pick(&mut self.rng, n, &[i, r1, r2]);
+                let (a, b, c) = (member(r1), member(r2), member(r3));
+                [0, 1, 2].map(|d| a[d] + f * (b[d] - c[d]))
+            }
+            Strategy::Best1Bin => {
+                let (best, a, b) = (member(0), member(r1), member(r2));
+                [0, 1, 2].map(|d| best[d] + f * (a[d] - b[d]))
+            }
+            Strategy::CurrentToBest1 => {
+                let (best, a, b) = (member(0), member(r1), member(r2));
+                [0, 1, 2].map(|d| target[d] + f * (best[d] - target[d]) + f * (a[d] - b[d]))
+            }
+        };
+
+        let forced = self.rng.random_range(0..3);
+        let [kp, ki, kd] = [0, 1, 2].map(|d| {
+            if d == forced || self.rng.random::<f32>() < self.crossover_rate {
+                mutant[d]
+            } else {
+                target[d]
+            }
+        });
+
+        Genome::new(kp, ki, kd).clamp(self.bounds)
+    }
+
+    fn record(&mut self, evaluations: usize, filtered: usize, start: Instant) {
+        if let Some(stats) = GenerationStats::new(self.generation, &self.population) {
+            let stats = stats.with_evaluations(evaluations, filtered, &self.cache, start);
+            self.history.push(stats);
+        }
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn len(&self) -> usize {
+        self.population.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.population.is_empty()
+    }
+
+    /// Current population, best first.
+    pub fn individuals(&self) -> &[Individual] {
+        self.population.individuals()
+    }
+}
+
+impl Optimizer for DifferentialEvolution {
+    fn initialise(&mut self) -> Result<(), OptimizerError> {
+        check_bounds(self.bounds)?;
+        let start = Instant::now();
+        let [max_kp, max_ki, max_kd] = self.bounds;
+
+        self.population = if self.parallel_works == 0 {
+            Population::new(
+                self.population_size,
+                self.objective.clone(),
+                self.dir.clone(),
+                max_kp,
+                max_ki,
+                max_kd,
+                self.seed,
+                self.cache.clone(),
+            )
+        } else {
+            Population::new_parallel(
+                self.population_size,
+                self.parallel_works,
+                self.objective.clone(),
+                self.dir.clone(),
+                max_kp,
+                max_ki,
+                max_kd,
+                self.seed,
+                self.cache.clone(),
+            )
+        };
+        self.generation = 0;
+        self.rng = ChaCha12Rng::seed_from_u64(self.seed);
+        self.history.clear();
+        if self.population.is_empty() {
+            return Err(OptimizerError::EmptyPopulation);
+        }
+        let filtered = self.population.filtered();
+        self.record(self.population_size, filtered, start);
+
+        Ok(())
+    }
+
+    fn step(&mut self) -> Result<Individual, OptimizerError> {
+        let n = self.population.len();
+        if n < 4 {
+            return Err(OptimizerError::Invalid(
+                "population_size",
+                format!("needs 4 individuals with a finite fitness, {n} left"),
+            ));
+        }
+
+        let start = Instant::now();
+        let previous_best = self.population.individuals()[0].fitness();
+        self.observers.generation_start(self.generation + 1);
+
+        let trials = (0..n).map(|i| self.trial(i)).collect::<Vec<_>>();
+        let trials = Population::evaluate(
+            trials,
+            self.parallel_works,
+            self.objective.clone(),
+            self.dir.clone(),
+            self.seed,
+            self.cache.clone(),
+        );
+
+        let filtered = trials
+            .iter()
+            .filter(|trial| !trial.fitness().is_finite())
+            .count();
+        let survivors = self
+            .population
+            .individuals()
+            .iter()
+            .zip(trials)
+            .map(|(target, trial)| {
+                if trial.fitness().is_finite() && trial.fitness() <= target.fitness() {
+                    trial
+                } else {
+                    target.clone()
+                }
+            })
+            .collect();
+        self.population = Population::from_individuals(survivors, self.seed);
+
+        self.generation += 1;
+        self.record(n, filtered, start);
+
+        let best = self
+            .population
+            .get_best()
+            .cloned()
+            .ok_or(OptimizerError::EmptyPopulation)?;
+
+        if let Some(stats) = self.history.last() {
+            self.observers
+                .generation_end(stats, &best, best.fitness() < previous_best);
+        }
+
+        Ok(best)
+    }
+
+    fn best(&self) -> Option<Individual> {
+        self.population.get_best().cloned()
+    }
+
+    fn statistics(&self) -> &[GenerationStats] {
+        &self.history
+    }
+
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    fn genomes(&self) -> Vec<Genome> {
+        self.population
+            .individuals()
+            .iter()
+            .map(|ind| ind.genome())
+            .collect()
+    }
+
+    fn observers(&mut self) -> &mut Observers {
+        &mut self.observers
+    }
+}
@@ -0,0 +1,541 @@
+use std::{
+    fmt, fs, io,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cache::FitnessCache,
+    cma_es::{CmaEsBuilder, DEFAULT_INITIAL_STEP},
+    differential_evolution::{
+        DEFAULT_CROSSOVER_RATE, DEFAULT_DIFFERENTIAL_WEIGHT, DifferentialEvolutionBuilder, Strategy,
+    },
+    genetic_algorithm::{DEFAULT_MUTATION_RATE, DEFAULT_REPLACE_RATE, GeneticAlgorithmBuilder},
+    individual::{Genome, Model},
+    local_search::Memetic,
+    metric::Metric,
+    optimizer::{Optimizer, OptimizerError},
+    population::Seeding,
+    pso::{DEFAULT_ACCELERATION, DEFAULT_INERTIA, ParticleSwarmBuilder},
+    sampling::Sampling,
+    scenario::{Disturbance, Objective, WeightedScenario},
+    stability::Screening,
+    trace::TraceOptions,
+    tuning,
+};
+
+#[derive(Debug)]
+pub enum ExperimentError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid(PathBuf, String),
+    Optimizer(PathBuf, OptimizerError),
+}
+
+impl fmt::Display for ExperimentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExperimentError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            ExperimentError::Parse(path, err) => write!(f, "{}: {}", path.display(), err),
+            ExperimentError::Invalid(path, msg) => write!(f, "{}: {}", path.display(), msg),
+            ExperimentError::Optimizer(path, err) => write!(f, "{}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for ExperimentError {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GaConfig {
+    #[serde(default = "default_population_size")]
+    pub population_size: usize,
+    #[serde(default = "default_parallel_works")]
+    pub parallel_works: usize,
+    #[serde(default = "default_generations")]
+    pub generations: usize,
+    pub mutation_step: f32,
+    #[serde(default = "default_mutation_rate")]
+    pub mutation_rate: f32,
+    #[serde(default = "default_replace_rate")]
+    pub replace_rate: f32,
+    pub digit_range: (i32, i32),
+    /// How the random part of the initial population is drawn.
+    #[serde(default)]
+    pub sampling: Sampling,
+    #[serde(default = "default_checkpoint_every")]
+    pub checkpoint_every: usize,
+}
+
+/// Language of the plot labels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    #[default]
+    English,
+    Portuguese,
+}
+
+/// Search strategy of an experiment.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Algorithm {
+    #[default]
+    Ga,
+    Pso,
+    De,
+    CmaEs,
+}
+
+impl Algorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Ga => "genetic algorithm",
+            Algorithm::Pso => "particle swarm",
+            Algorithm::De => "differential evolution",
+            Algorithm::CmaEs => "CMA-ES",
+        }
+    }
+}
+
+/// Coefficients of the particle swarm; the sizes are taken from `[ga]`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PsoConfig {
+    #[serde(default = "default_inertia")]
+    pub inertia: f32,
+    #[serde(default = "default_acceleration")]
+    pub cognitive: f32,
+    #[serde(default = "default_acceleration")]
+    pub social: f32,
+}
+
+impl Default for PsoConfig {
+    fn default() -> Self {
+        Self {
+            inertia: DEFAULT_INERTIA,
+            cognitive: DEFAULT_ACCELERATION,
+            social: DEFAULT_ACCELERATION,
+        }
+    }
+}
+
+/// Differential evolution settings; the sizes are taken from `[ga]`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeConfig {
+    #[serde(default)]
+    pub strategy: Strategy,
+    /// F, the scale of the difference vectors.
+    #[serde(default = "default_differential_weight")]
+    pub differential_weight: f32,
+    /// CR, the chance of taking each gain from the mutant.
+    #[serde(default = "default_crossover_rate")]
+    pub crossover_rate: f32,
+}
+
+impl Default for DeConfig {
+    fn default() -> Self {
+        Self {
+            strategy: Strategy::default(),
+            differential_weight: DEFAULT_DIFFERENTIAL_WEIGHT,
+            crossover_rate: DEFAULT_CROSSOVER_RATE,
+        }
+    }
+}
+
+/// CMA-ES settings, as the optimiser or as a refinement stage.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CmaEsConfig {
+    /// Offspring per generation; `4 + 3 ln n` for `n` searched gains if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub population_size: Option<usize>,
+    /// Initial step size as a fraction of the bounds.
+    #[serde(default = "default_initial_step")]
+    pub initial_step: f32,
+    /// Generations of CMA-ES refinement after another optimiser; 0 disables it.
+    #[serde(default)]
+    pub refine_generations: usize,
+    /// Initial step size of the refinement stage.
+    #[serde(default = "default_refine_step")]
+    pub refine_step: f32,
+}
+
+impl Default for CmaEsConfig {
+    fn default() -> Self {
+        Self {
+            population_size: None,
+            initial_step: DEFAULT_INITIAL_STEP,
+            refine_generations: 0,
+            refine_step: default_refine_step(),
+        }
+    }
+}
+
+/// Repeats an experiment over several seeds derived from `seed`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeedsConfig {
+    pub runs: usize,
+    /// Runs optimised at the same time.
+    #[serde(default = "default_parallel_runs")]
+    pub parallel: usize,
+    /// Final fitness at or below which a run counts as a success.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<f32>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bounds {
+    #[serde(default)]
+    pub max_kp: f32,
+    #[serde(default)]
+    pub max_ki: f32,
+    #[serde(default)]
+    pub max_kd: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Experiment {
+    pub output_dir: String,
+    pub seed: u64,
+    pub plant: Model,
+    #[serde(default)]
+    pub metric: Metric,
+    #[serde(default)]
+    pub language: Language,
+    #[serde(default = "default_dt")]
+    pub dt: f32,
+    #[serde(default = "default_duration")]
+    pub duration: f32,
+    #[serde(default = "default_scenarios")]
+    pub scenarios: Vec<WeightedScenario>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disturbance: Option<Disturbance>,
+    /// Standard deviation of the measurement noise added to the fed back output.
+    #[serde(default)]
+    pub noise: f32,
+    /// Stability check of candidate gains before they are simulated.
+    #[serde(default, skip_serializing_if = "Screening::is_off")]
+    pub screening: Screening,
+    #[serde(default)]
+    pub optimizer: Algorithm,
+    pub ga: GaConfig,
+    #[serde(default)]
+    pub pso: PsoConfig,
+    #[serde(default)]
+    pub de: DeConfig,
+    #[serde(default)]
+    pub cma_es: CmaEsConfig,
+    /// Polishes the best individuals of the GA with a local search.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memetic: Option<Memetic>,
+    /// Starts part of the GA's initial population from known gains.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seeding: Option<Seeding>,
+    /// Adds the classical tunings of the plant to the seeded genomes.
+    #[serde(default)]
+    pub inject_baselines: bool,
+    /// Runs the experiment over several seeds instead of `seed` alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seeds: Option<SeedsConfig>,
+    /// Seed of the random scenarios and the noise, `seed` when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub objective_seed: Option<u64>,
+    pub bounds: Bounds,
+    #[serde(default)]
+    pub trace: TraceOptions,
+}
+
+fn default_population_size() -> usize {
+    1_000
+}
+
+fn default_parallel_works() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+fn default_generations() -> usize {
+    100
+}
+
+fn default_mutation_rate() -> f32 {
+    DEFAULT_MUTATION_RATE
+}
+
+fn default_replace_rate() -> f32 {
+    DEFAULT_REPLACE_RATE
+}
+
+fn default_inertia() -> f32 {
+    DEFAULT_INERTIA
+}
+
+fn default_acceleration() -> f32 {
+    DEFAULT_ACCELERATION
+}
+
+fn default_differential_weight() -> f32 {
+    DEFAULT_DIFFERENTIAL_WEIGHT
+}
+
+fn default_crossover_rate() -> f32 {
+    DEFAULT_CROSSOVER_RATE
+}
+
+fn default_initial_step() -> f32 {
+    DEFAULT_INITIAL_STEP
+}
+
+fn default_refine_step() -> f32 {
+    0.05
+}
+
+fn default_parallel_runs() -> usize {
+    1
+}
+
+fn default_checkpoint_every() -> usize {
+    10
+}
+
+fn default_dt() -> f32 {
+    Objective::default().dt
+}
+
+fn default_duration() -> f32 {
+    Objective::default().duration
+}
+
+fn default_scenarios() -> Vec<WeightedScenario> {
+    Objective::default().scenarios
+}
+
+impl Experiment {
+    pub fn load(path: &str) -> Result<Self, ExperimentError> {
+        let path = PathBuf::from(path);
+        let text =
+            fs::read_to_string(&path).map_err(|err| ExperimentError::Io(path.clone(), err))?;
+        let experiment: Experiment =
+            toml::from_str(&text).map_err(|err| ExperimentError::Parse(path.clone(), err))?;
+        experiment
+            .validate()
+            .map_err(|msg| ExperimentError::Invalid(path.clone(), msg))?;
+        experiment
+            .validate_optimizer()
+            .map_err(|err| ExperimentError::Optimizer(path, err))?;
+
+        Ok(experiment)
+    }
+
+    pub fn objective(&self) -> Objective {
+        Objective {
+            model: self.plant,
+            metric: self.metric,
+            dt: self.dt,
+            duration: self.duration,
+            scenarios: self.scenarios.clone(),
+            disturbance: self.disturbance,
+            noise: self.noise,
+            screening: self.screening,
+            seed: self.objective_seed,
+        }
+    }
+
+    pub fn builder(&self) -> GeneticAlgorithmBuilder {
+        let builder = GeneticAlgorithmBuilder::default()
+            .with_population_size(self.ga.population_size)
+            .with_parallel_works(self.ga.parallel_works)
+            .with_objective(self.objective())
+            .with_mutation_step(self.ga.mutation_step)
+            .with_mutation_rate(self.ga.mutation_rate)
+            .with_replace_rate(self.ga.replace_rate)
+            .with_digit_range(self.ga.digit_range)
+            .with_sampling(self.ga.sampling)
+            .with_output_dir(self.output_dir.as_str())
+            .with_max_kp(self.bounds.max_kp)
+            .with_max_ki(self.bounds.max_ki)
+            .with_max_kd(self.bounds.max_kd)
+            .with_seed(self.seed);
+        let builder = match self.memetic {
+            Some(memetic) => builder.with_memetic(memetic),
+            None => builder,
+        };
+
+        let mut seeding = self.seeding.clone().unwrap_or_default();
+        if self.inject_baselines {
+            seeding.genomes.extend(
+                tuning::tunings(self.plant)
+                    .into_iter()
+                    .map(|tuning| tuning.genome),
+            );
+        }
+        if seeding.genomes.is_empty() {
+            builder
+        } else {
+            builder.with_seeding(seeding)
+        }
+    }
+
+    pub fn pso_builder(&self) -> ParticleSwarmBuilder {
+        ParticleSwarmBuilder::default()
+            .with_swarm_size(self.ga.population_size)
+            .with_parallel_works(self.ga.parallel_works)
+            .with_objective(self.objective())
+            .with_output_dir(self.output_dir.as_str())
+            .with_max_kp(self.bounds.max_kp)
+            .with_max_ki(self.bounds.max_ki)
+            .with_max_kd(self.bounds.max_kd)
+            .with_inertia(self.pso.inertia)
+            .with_cognitive(self.pso.cognitive)
+            .with_social(self.pso.social)
+            .with_seed(self.seed)
+    }
+
+    pub fn de_builder(&self) -> DifferentialEvolutionBuilder {
+        DifferentialEvolutionBuilder::default()
+            .with_population_size(self.ga.population_size)
+            .with_parallel_works(self.ga.parallel_works)
+            .with_objective(self.objective())
+            .with_output_dir(self.output_dir.as_str())
+            .with_max_kp(self.bounds.max_kp)
+            .with_max_ki(self.bounds.max_ki)
+            .with_max_kd(self.bounds.max_kd)
+            .with_strategy(self.de.strategy)
+            .with_differential_weight(self.de.differential_weight)
+            .with_crossover_rate(self.de.crossover_rate)
+            .with_seed(self.seed)
+    }
+
+    pub fn cma_es_builder(&self) -> CmaEsBuilder {
+        let builder = CmaEsBuilder::default()
+            .with_parallel_works(self.ga.parallel_works)
+            .with_objective(self.objective())
+            .with_output_dir(self.output_dir.as_str())
+            .with_max_kp(self.bounds.max_kp)
+            .with_max_ki(self.bounds.max_ki)
+            .with_max_kd(self.bounds.max_kd)
+            .with_initial_step(self.cma_es.initial_step)
+            .with_seed(self.seed);
+        match self.cma_es.population_size {
+            Some(size) => builder.with_population_size(size),
+            None => builder,
+        }
+    }
+
+    /// CMA-ES refining `start`, found at `generation` by another optimiser.
+    pub fn refine_builder(&self, start: Genome, generation: usize) -> CmaEsBuilder {
+        self.cma_es_builder()
+            .with_initial_step(self.cma_es.refine_step)
+            .with_start(start)
+            .with_start_generation(generation)
+    }
+
+    /// Builds the configured optimiser writing to `dir`.
+    pub fn optimizer(
+        &self,
+        dir: Arc<str>,
+        cache: FitnessCache,
+    ) -> Result<Box<dyn Optimizer>, OptimizerError> {
+        Ok(match self.optimizer {
+            Algorithm::Ga => Box::new(
+                self.builder()
+                    .with_output_dir(dir)
+                    .with_fitness_cache(cache)
+                    .build()?,
+            ),
+            Algorithm::Pso => Box::new(
+                self.pso_builder()
+                    .with_output_dir(dir)
+                    .with_fitness_cache(cache)
+                    .build()?,
+            ),
+            Algorithm::De => Box::new(
+                self.de_builder()
+                    .with_output_dir(dir)
+                    .with_fitness_cache(cache)
+                    .build()?,
+            ),
+            Algorithm::CmaEs => Box::new(
+                self.cma_es_builder()
+                    .with_output_dir(dir)
+                    .with_fitness_cache(cache)
+                    .build()?,
+            ),
+        })
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let text = toml::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, text)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let dir = Path::new(&self.output_dir);
+        if self.output_dir.is_empty()
+            || !dir
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(format!(
+                "output_dir `{}` must be a relative path without `..`",
+                self.output_dir
+            ));
+        }
+
+        if self.ga.generations == 0 {
+            return Err("ga.generations must be greater than 0".to_string());
+        }
+
+        if self.memetic.is_some() && self.optimizer != Algorithm::Ga {
+            return Err("memetic refinement needs optimizer = \"ga\"".to_string());
+        }
+        if self.seeding.is_some() && self.optimizer != Algorithm::Ga {
+            return Err("seeding needs optimizer = \"ga\"".to_string());
+        }
+        if self.ga.sampling != Sampling::Uniform && self.optimizer != Algorithm::Ga {
+            return Err("ga.sampling needs optimizer = \"ga\"".to_string());
+        }
+        if self.inject_baselines && self.optimizer != Algorithm::Ga {
+            return Err("inject_baselines needs optimizer = \"ga\"".to_string());
+        }
+        if let Some(seeds) = &self.seeds {
+            if seeds.runs == 0 {
+                return Err("seeds.runs must be at least 1".to_string());
+            }
+            if seeds.parallel == 0 {
+                return Err("seeds.parallel must be at least 1".to_string());
+            }
+            if seeds.target.is_some_and(|target| !target.is_finite()) {
+                return Err("seeds.target must be a number".to_string());
+            }
+        }
+
+        if self.trace.decimate == 0 {
+            return Err("trace.decimate must be at least 1".to_string());
+        }
+
+        Ok(())
+    }
+
+    // Checks the optimiser settings with the builders the run will use.
+    fn validate_optimizer(&self) -> Result<(), OptimizerError> {
+        match self.optimizer {
+            Algorithm::Ga => self.builder().validate()?,
+            Algorithm::Pso => self.pso_builder().validate()?,
+            Algorithm::De => self.de_builder().validate()?,
+            Algorithm::CmaEs => self.cma_es_builder().validate()?,
+        }
+        if self.cma_es.refine_generations > 0 && self.optimizer != Algorithm::CmaEs {
+            self.refine_builder(Genome::new(0.0, 0.0, 0.0), 0)
+                .validate()?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,135 +1,454 @@
-use rand::{SeedableRng, rngs::StdRng};
+use std::{io, path::PathBuf, sync::Arc, time::Instant};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
 
 use crate::{
-    individual::{Individual, Model},
-    population::Population,
+    cache::FitnessCache,
+    checkpoint::Checkpoint,
+    individual::{Genome, Individual},
+    local_search::{Memetic, Polish},
+    observer::{Observer, Observers},
+    optimizer::{Optimizer, OptimizerError, check_bounds, check_rate},
+    population::{MAX_POPULATION_SIZE, Population, Seeding},
+    sampling::Sampling,
+    scenario::Objective,
+    stats::GenerationStats,
+    work::{work_pool, work_serial},
 };
 
+/// Mutation rate used when the builder sets none.
+pub const DEFAULT_MUTATION_RATE: f32 = 0.75;
+/// Replace rate used when the builder sets none.
+pub const DEFAULT_REPLACE_RATE: f32 = 0.3;
+
+/// Steady-state genetic algorithm over PID gains.
 pub struct GeneticAlgorithm {
     population: Population,
     generation: usize,
+    population_size: usize,
+    parallel_works: usize,
+    objective: Arc<Objective>,
+    dir: Arc<str>,
+    bounds: [f32; 3],
     mutation_step: f32,
+    mutation_rate: f32,
+    replace_rate: f32,
     digit_range: (i32, i32),
     seed: u64,
-    rng: StdRng,
+    rng: ChaCha12Rng,
+    checkpoint: Option<PathBuf>,
+    checkpoint_every: usize,
+    cache: FitnessCache,
+    history: Vec<GenerationStats>,
+    memetic: Option<Memetic>,
+    polished_at: Option<usize>,
+    seeding: Option<Seeding>,
+    sampling: Sampling,
+    observers: Observers,
 }
 
+/// Configures and builds a [`GeneticAlgorithm`], evaluating its initial population.
 #[derive(Default)]
 pub struct GeneticAlgorithmBuilder {
-    population_size: usize,
+    population_size: Option<usize>,
     parellel_works: usize,
+    mutation_step: Option<f32>,
+    mutation_rate: Option<f32>,
+    replace_rate: Option<f32>,
+    objective: Objective,
+    digit_range: Option<(i32, i32)>,
+    dir: Arc<str>,
+    max_kp: Option<f32>,
+    max_ki: Option<f32>,
+    max_kd: Option<f32>,
+    seed: u64,
+    checkpoint: Option<PathBuf>,
+    checkpoint_every: usize,
+    cache: FitnessCache,
+    memetic: Option<Memetic>,
+    seeding: Option<Seeding>,
+    sampling: Sampling,
+    observers: Observers,
+}
+
+// Required builder parameters once checked.
+struct Settings {
+    population_size: usize,
     mutation_step: f32,
-    model: Model,
+    mutation_rate: f32,
+    replace_rate: f32,
     digit_range: (i32, i32),
-    dir: &'static str,
-    max_kp: f32,
-    max_ki: f32,
-    max_kd: f32,
-    seed: u64,
+    bounds: [f32; 3],
 }
 
 impl GeneticAlgorithmBuilder {
+    /// Number of individuals in the initial population.
     pub fn with_population_size(mut self, size: usize) -> Self {
-        self.population_size = size;
+        self.population_size = Some(size);
         self
     }
 
+    /// Worker threads; 0 evaluates on the calling thread.
     pub fn with_parallel_works(mut self, works: usize) -> Self {
         self.parellel_works = works;
         self
     }
 
-    pub fn with_model(mut self, model: Model) -> Self {
-        self.model = model;
+    /// Plant, metric and scenarios individuals are scored against.
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
         self
     }
 
+    /// Largest change a mutation applies to one gain.
     pub fn with_mutation_step(mut self, step: f32) -> Self {
-        self.mutation_step = step;
+        self.mutation_step = Some(step);
+        self
+    }
+
+    /// Chance of mutating each gain of a child in [`Optimizer::step`].
+    pub fn with_mutation_rate(mut self, rate: f32) -> Self {
+        self.mutation_rate = Some(rate);
         self
     }
 
+    /// Fraction of the population replaced in [`Optimizer::step`].
+    pub fn with_replace_rate(mut self, rate: f32) -> Self {
+        self.replace_rate = Some(rate);
+        self
+    }
+
+    /// Powers of ten of the digits exchanged by crossover.
     pub fn with_digit_range(mut self, range: (i32, i32)) -> Self {
-        self.digit_range = range;
+        self.digit_range = Some(range);
         self
     }
 
-    pub fn with_output_dir(mut self, dir: &'static str) -> Self {
-        self.dir = dir;
+    /// Directory under `output/` where traces of the best individual are written.
+    pub fn with_output_dir(mut self, dir: impl Into<Arc<str>>) -> Self {
+        self.dir = dir.into();
         self
     }
 
+    /// Upper bound of kp in the initial population.
     pub fn with_max_kp(mut self, max_kp: f32) -> Self {
-        self.max_kp = max_kp;
+        self.max_kp = Some(max_kp);
         self
     }
 
+    /// Upper bound of ki in the initial population.
     pub fn with_max_ki(mut self, max_ki: f32) -> Self {
-        self.max_ki = max_ki;
+        self.max_ki = Some(max_ki);
         self
     }
 
+    /// Upper bound of kd in the initial population.
     pub fn with_max_kd(mut self, max_kd: f32) -> Self {
-        self.max_kd = max_kd;
+        self.max_kd = Some(max_kd);
         self
     }
 
+    /// Seed of every random stream.
     pub fn with_seed(mut self, seed: u64) -> Self {
         self.seed = seed;
         self
     }
 
-    pub fn build(self) -> GeneticAlgorithm {
-        let rng = StdRng::seed_from_u64(self.seed);
+    /// Saves the GA state to `path` every `every` generations.
+    pub fn with_checkpoint(mut self, path: &str, every: usize) -> Self {
+        self.checkpoint = Some(PathBuf::from(path));
+        self.checkpoint_every = every;
+        self
+    }
 
-        GeneticAlgorithm {
-            population: if self.parellel_works == 0 {
-                Population::new(
-                    self.population_size,
-                    self.model,
-                    self.dir,
-                    self.max_kp,
-                    self.max_ki,
-                    self.max_kd,
-                    self.seed,
-                )
-            } else {
-                Population::new_parallel(
-                    self.population_size,
-                    self.parellel_works,
-                    self.model,
-                    self.dir,
-                    self.max_kp,
-                    self.max_ki,
-                    self.max_kd,
-                    self.seed,
-                )
-            },
+    /// Cache consulted before simulating a genome.
+    pub fn with_fitness_cache(mut self, cache: FitnessCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Polishes the best individuals with a local search; see [`Memetic`].
+    pub fn with_memetic(mut self, memetic: Memetic) -> Self {
+        self.memetic = Some(memetic);
+        self
+    }
+
+    /// How the random part of the initial population is drawn.
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Starts part of the initial population from known gains.
+    pub fn with_seeding(mut self, seeding: Seeding) -> Self {
+        self.seeding = Some(seeding);
+        self
+    }
+
+    /// Checks the settings without evaluating anything.
+    pub fn validate(&self) -> Result<(), OptimizerError> {
+        self.settings().map(|_| ())
+    }
+
+    fn settings(&self) -> Result<Settings, OptimizerError> {
+        let population_size = self
+            .population_size
+            .ok_or(OptimizerError::Missing("population_size"))?;
+        let mutation_step = self
+            .mutation_step
+            .ok_or(OptimizerError::Missing("mutation_step"))?;
+        let mutation_rate = self.mutation_rate.unwrap_or(DEFAULT_MUTATION_RATE);
+        let replace_rate = self.replace_rate.unwrap_or(DEFAULT_REPLACE_RATE);
+        let digit_range = self
+            .digit_range
+            .ok_or(OptimizerError::Missing("digit_range"))?;
+        let bounds = [
+            self.max_kp.ok_or(OptimizerError::Missing("max_kp"))?,
+            self.max_ki.ok_or(OptimizerError::Missing("max_ki"))?,
+            self.max_kd.ok_or(OptimizerError::Missing("max_kd"))?,
+        ];
+
+        if !(2..=MAX_POPULATION_SIZE).contains(&population_size) {
+            return Err(OptimizerError::Invalid(
+                "population_size",
+                format!("must be between 2 and {MAX_POPULATION_SIZE}, got {population_size}"),
+            ));
+        }
+        if !(mutation_step.is_finite() && mutation_step >= 0.0) {
+            return Err(OptimizerError::Invalid(
+                "mutation_step",
+                format!("must be a non-negative number, got {mutation_step}"),
+            ));
+        }
+        check_rate("mutation_rate", mutation_rate)?;
+        check_rate("replace_rate", replace_rate)?;
+        if digit_range.0 > digit_range.1 {
+            return Err(OptimizerError::Invalid(
+                "digit_range",
+                format!("[{}, {}] is empty", digit_range.0, digit_range.1),
+            ));
+        }
+        if self.checkpoint.is_some() && self.checkpoint_every == 0 {
+            return Err(OptimizerError::Invalid(
+                "checkpoint_every",
+                "must be at least 1 when checkpointing".to_string(),
+            ));
+        }
+
+        check_bounds(bounds)?;
+        if let Some(memetic) = &self.memetic {
+            memetic
+                .validate()
+                .map_err(|msg| OptimizerError::Invalid("memetic", msg))?;
+        }
+        if let Some(seeding) = &self.seeding {
+            seeding
+                .validate()
+                .map_err(|msg| OptimizerError::Invalid("seeding", msg))?;
+        }
+
+        self.objective
+            .validate()
+            .map_err(OptimizerError::Objective)?;
+
+        Ok(Settings {
+            population_size,
+            mutation_step,
+            mutation_rate,
+            replace_rate,
+            digit_range,
+            bounds,
+        })
+    }
+
+    /// Adds an observer notified of every generation; see [`Observer`].
+    pub fn with_observer(mut self, observer: impl Observer + 'static) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    /// Evaluates the initial population and returns the GA at generation 0.
+    pub fn build(self) -> Result<GeneticAlgorithm, OptimizerError> {
+        let Settings {
+            population_size,
+            mutation_step,
+            mutation_rate,
+            replace_rate,
+            digit_range,
+            bounds,
+        } = self.settings()?;
+
+        let mut ga = GeneticAlgorithm {
+            population: Population::from_parts(vec![], ChaCha12Rng::seed_from_u64(self.seed)),
             generation: 0,
+            population_size,
+            parallel_works: self.parellel_works,
+            objective: Arc::new(self.objective),
+            dir: self.dir.clone(),
+            bounds,
+            mutation_step,
+            mutation_rate,
+            replace_rate,
+            digit_range,
+            seed: self.seed,
+            rng: ChaCha12Rng::seed_from_u64(self.seed),
+            checkpoint: self.checkpoint,
+            checkpoint_every: self.checkpoint_every,
+            cache: self.cache,
+            history: vec![],
+            memetic: self.memetic,
+            polished_at: None,
+            seeding: self.seeding,
+            sampling: self.sampling,
+            observers: self.observers,
+        };
+        ga.initialise()?;
+
+        Ok(ga)
+    }
+}
+
+impl GeneticAlgorithm {
+    /// Restores and validates a GA saved by [`save_checkpoint`](Self::save_checkpoint).
+    pub fn resume(path: &str, cache: FitnessCache) -> io::Result<Self> {
+        let checkpoint = Checkpoint::load(path.as_ref())?;
+        let [max_kp, max_ki, max_kd] = checkpoint.bounds;
+        let mut builder = GeneticAlgorithmBuilder::default()
+            .with_population_size(checkpoint.population_size)
+            .with_objective(checkpoint.objective.clone())
+            .with_mutation_step(checkpoint.mutation_step)
+            .with_mutation_rate(checkpoint.mutation_rate)
+            .with_replace_rate(checkpoint.replace_rate)
+            .with_digit_range(checkpoint.digit_range)
+            .with_max_kp(max_kp)
+            .with_max_ki(max_ki)
+            .with_max_kd(max_kd)
+            .with_checkpoint(path, checkpoint.checkpoint_every);
+        builder.memetic = checkpoint.memetic;
+        builder.seeding = checkpoint.seeding.clone();
+        builder.validate().map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid checkpoint {path}: {err}"),
+            )
+        })?;
+
+        let dir: Arc<str> = checkpoint.dir.into();
+        let objective = Arc::new(checkpoint.objective);
+        let individuals = checkpoint
+            .individuals
+            .into_iter()
+            .map(|(genome, fitness)| {
+                Individual::with_fitness(
+                    genome,
+                    fitness,
+                    objective.clone(),
+                    dir.clone(),
+                    checkpoint.seed,
+                )
+            })
+            .collect();
+
+        Ok(GeneticAlgorithm {
+            population: Population::from_parts(individuals, checkpoint.population_rng),
+            generation: checkpoint.generation,
+            population_size: checkpoint.population_size,
+            parallel_works: checkpoint.parallel_works,
+            objective,
+            dir,
+            bounds: checkpoint.bounds,
+            mutation_step: checkpoint.mutation_step,
+            mutation_rate: checkpoint.mutation_rate,
+            replace_rate: checkpoint.replace_rate,
+            digit_range: checkpoint.digit_range,
+            seed: checkpoint.seed,
+            rng: checkpoint.rng,
+            checkpoint: Some(PathBuf::from(path)),
+            checkpoint_every: checkpoint.checkpoint_every,
+            cache,
+            history: checkpoint.history,
+            memetic: checkpoint.memetic,
+            polished_at: checkpoint.polished_at,
+            seeding: checkpoint.seeding,
+            sampling: checkpoint.sampling,
+            observers: Observers::default(),
+        })
+    }
+
+    /// Writes the checkpoint configured on the builder, if any.
+    pub fn save_checkpoint(&self) -> io::Result<()> {
+        let Some(path) = &self.checkpoint else {
+            return Ok(());
+        };
+
+        Checkpoint {
+            population_size: self.population_size,
+            parallel_works: self.parallel_works,
+            objective: self.objective.as_ref().clone(),
+            dir: self.dir.to_string(),
+            bounds: self.bounds,
             mutation_step: self.mutation_step,
+            mutation_rate: self.mutation_rate,
+            replace_rate: self.replace_rate,
             digit_range: self.digit_range,
             seed: self.seed,
-            rng,
+            checkpoint_every: self.checkpoint_every,
+            generation: self.generation,
+            individuals: self
+                .population
+                .individuals()
+                .iter()
+                .map(|ind| (ind.genome(), ind.fitness()))
+                .collect(),
+            population_rng: self.population.rng().clone(),
+            rng: self.rng.clone(),
+            history: self.history.clone(),
+            memetic: self.memetic,
+            polished_at: self.polished_at,
+            seeding: self.seeding.clone(),
+            sampling: self.sampling,
         }
+        .save(path)
     }
-}
 
-impl GeneticAlgorithm {
-    pub fn seed(&self) -> u64 {
-        self.seed
+    /// Persists the fitness cache to the file it was loaded from.
+    pub fn save_fitness_cache(&self) -> io::Result<()> {
+        self.cache.save()
     }
 
-    pub fn generation(&self) -> usize {
-        self.generation
+    // Appends the statistics of the current population to the history.
+    fn record(&mut self, evaluations: usize, filtered: usize, start: Instant) {
+        if let Some(stats) = GenerationStats::new(self.generation, &self.population) {
+            let stats = stats.with_evaluations(evaluations, filtered, &self.cache, start);
+            self.history.push(stats);
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
     }
 
     pub fn len(&self) -> usize {
         self.population.len()
     }
 
-    pub fn tournament_section(&mut self, tournament_size: usize) -> Population {
+    pub fn is_empty(&self) -> bool {
+        self.population.is_empty()
+    }
+
+    /// Current population, best first.
+    pub fn individuals(&self) -> &[Individual] {
+        self.population.individuals()
+    }
+
+    /// Picks `len / tournament_size` winners of random tournaments.
+    pub fn tournament_section(
+        &mut self,
+        tournament_size: usize,
+    ) -> Result<Population, OptimizerError> {
         if self.population.len() < tournament_size {
-            return self.population.clone();
+            return Ok(self.population.clone());
         }
 
         let mut selected = vec![];
@@ -139,39 +458,37 @@ impl GeneticAlgorithm {
                 .map(|_| self.population.get_random_individual())
                 .collect::<Vec<_>>();
 
+            if let Some(fighter) = fighters.iter().find(|ind| !ind.fitness().is_finite()) {
+                return Err(OptimizerError::NonFiniteFitness(fighter.fitness()));
+            }
             let winner = fighters
                 .into_iter()
-                .max_by(|a, b| {
-                    let res = b.partial_cmp(a);
-                    if res.is_none() {
-                        println!(
-                            "Warning: NaN fitness detected during tournament selection: {} vs {}",
-                            a.fitness(),
-                            b.fitness()
-                        );
-                    }
-                    res.unwrap()
-                })
-                .unwrap();
+                .max_by(|a, b| b.fitness().total_cmp(&a.fitness()))
+                .ok_or(OptimizerError::EmptyPopulation)?;
 
             selected.push(winner);
         }
 
-        Population::from_individuals(selected, self.seed)
+        Ok(Population::from_individuals(selected, self.seed))
     }
 
-    pub fn eval(&mut self, mutation_rate: f32, replace_rate: f32) -> Option<Individual> {
-        assert!(
-            0.0 <= mutation_rate && mutation_rate <= 1.0,
-            "Mutation rate must be between 0 and 1"
-        );
-        assert!(
-            0.0 <= replace_rate && replace_rate <= 1.0,
-            "Replace rate must be between 0 and 1"
-        );
+    /// Breeds the next generation and returns its best individual.
+    pub fn eval(
+        &mut self,
+        mutation_rate: f32,
+        replace_rate: f32,
+    ) -> Result<Individual, OptimizerError> {
+        check_rate("mutation_rate", mutation_rate)?;
+        check_rate("replace_rate", replace_rate)?;
+        if self.population.is_empty() {
+            return Err(OptimizerError::EmptyPopulation);
+        }
 
-        println!("Selection by tournament...");
-        let mut to_reproduce = self.tournament_section(3);
+        let start = Instant::now();
+        let previous_best = self.population.get_best().map(|ind| ind.fitness());
+        self.observers.generation_start(self.generation + 1);
+
+        let mut to_reproduce = self.tournament_section(3)?;
 
         let mut all_children = vec![];
         let total_crossovers = to_reproduce.len() / 2;
@@ -180,26 +497,217 @@ impl GeneticAlgorithm {
                 break;
             };
 
+            let (father, mother) = (father.genome(), mother.genome());
             let children = father.crossover(&mother, self.digit_range, &mut self.rng);
             all_children.extend(children);
         }
 
-        let all_children = Population::from_individuals(
-            all_children
-                .into_iter()
-                .map(|child| child.mutate(mutation_rate, self.mutation_step, &mut self.rng))
-                .collect::<Vec<_>>()
-                .into(),
+        let all_children = all_children
+            .into_iter()
+            .map(|child| child.mutate(mutation_rate, self.mutation_step, &mut self.rng))
+            .collect::<Vec<Genome>>();
+
+        let mut evaluations = all_children.len();
+        let all_children = Population::from_genomes(
+            all_children,
+            self.parallel_works,
+            self.objective.clone(),
+            self.dir.clone(),
             self.seed,
+            self.cache.clone(),
         );
 
-        println!("Replacing...");
+        let filtered = all_children.filtered();
         let n_retain = (self.population.len() as f32 * (1.0 - replace_rate)) as usize;
         let best_parents = self.population.get_nth_bests(n_retain);
         self.population = best_parents.merge(all_children);
 
         self.generation += 1;
+        if let Some(memetic) = self
+            .memetic
+            .filter(|memetic| memetic.every > 0 && self.generation.is_multiple_of(memetic.every))
+        {
+            evaluations += self.polish(&memetic);
+        }
+        self.record(evaluations, filtered, start);
+
+        if self.checkpoint_every > 0 && self.generation.is_multiple_of(self.checkpoint_every) {
+            log::debug!("Saving checkpoint...");
+            if let Err(err) = self.save_checkpoint() {
+                log::error!("Error saving checkpoint: {err}");
+            }
+        }
+
+        self.notify(previous_best)
+    }
+
+    // Polishes the best individuals in place, returning the evaluations spent.
+    fn polish(&mut self, memetic: &Memetic) -> usize {
+        let elites = self
+            .population
+            .get_nth_bests(memetic.elites)
+            .individuals()
+            .to_vec();
+        let polish = Polish {
+            memetic: *memetic,
+            bounds: self.bounds,
+            objective: self.objective.clone(),
+            dir: self.dir.clone(),
+            seed: self.seed,
+            cache: self.cache.clone(),
+        };
+        let polished = if self.parallel_works == 0 {
+            work_serial(elites, polish)
+        } else {
+            work_pool(self.parallel_works, elites, polish)
+        };
+
+        let evaluations = polished.iter().map(|(_, evaluations)| evaluations).sum();
+        let polished = polished.into_iter().map(|(ind, _)| ind).collect::<Vec<_>>();
+        log::debug!(
+            "Polished {} elites with {evaluations} evaluations",
+            polished.len()
+        );
+        self.population = self.population.clone().replace_bests(polished);
+        self.polished_at = Some(self.generation);
+
+        evaluations
+    }
+
+    // Reports the generation just recorded to the observers.
+    fn notify(&mut self, previous_best: Option<f32>) -> Result<Individual, OptimizerError> {
+        let best = self
+            .population
+            .get_best()
+            .cloned()
+            .ok_or(OptimizerError::EmptyPopulation)?;
+
+        if let Some(stats) = self.history.last() {
+            let improved = previous_best.is_none_or(|fitness| best.fitness() < fitness);
+            self.observers.generation_end(stats, &best, improved);
+        }
+
+        Ok(best)
+    }
+}
+
+impl Optimizer for GeneticAlgorithm {
+    fn initialise(&mut self) -> Result<(), OptimizerError> {
+        check_bounds(self.bounds)?;
+        let start = Instant::now();
+        let [max_kp, max_ki, max_kd] = self.bounds;
+
+        let seeded = self.seeding.as_ref().map_or(vec![], |seeding| {
+            seeding.sample(self.population_size, self.bounds, self.seed)
+        });
+        let random = self.population_size - seeded.len();
+
+        self.population = match self.sampling {
+            Sampling::Uniform if self.parallel_works == 0 => Population::new(
+                random,
+                self.objective.clone(),
+                self.dir.clone(),
+                max_kp,
+                max_ki,
+                max_kd,
+                self.seed,
+                self.cache.clone(),
+            ),
+            Sampling::Uniform => Population::new_parallel(
+                random,
+                self.parallel_works,
+                self.objective.clone(),
+                self.dir.clone(),
+                max_kp,
+                max_ki,
+                max_kd,
+                self.seed,
+                self.cache.clone(),
+            ),
+            sampling => Population::from_genomes(
+                sampling.sample(random, self.bounds, self.seed),
+                self.parallel_works,
+                self.objective.clone(),
+                self.dir.clone(),
+                self.seed,
+                self.cache.clone(),
+            ),
+        };
+        let mut filtered = self.population.filtered();
+        if !seeded.is_empty() {
+            let seeded = Population::evaluate(
+                seeded,
+                self.parallel_works,
+                self.objective.clone(),
+                self.dir.clone(),
+                self.seed,
+                self.cache.clone(),
+            );
+            let seeded = Population::from_parts(seeded, self.population.rng().clone());
+            self.population = self.population.clone().merge(seeded);
+            filtered += self.population.filtered();
+        }
+        self.generation = 0;
+        self.rng = ChaCha12Rng::seed_from_u64(self.seed);
+        self.history.clear();
+        if self.population.is_empty() {
+            return Err(OptimizerError::EmptyPopulation);
+        }
+        self.record(self.population_size, filtered, start);
+
+        Ok(())
+    }
+
+    fn step(&mut self) -> Result<Individual, OptimizerError> {
+        self.eval(self.mutation_rate, self.replace_rate)
+    }
+
+    /// Polishes the final population once if [`Memetic::at_end`] is set.
+    fn finish(&mut self) -> Result<(), OptimizerError> {
+        let Some(memetic) = self.memetic.filter(|memetic| memetic.at_end) else {
+            return Ok(());
+        };
+        if self.polished_at == Some(self.generation) {
+            return Ok(());
+        }
+
+        let start = Instant::now();
+        let previous_best = self.population.get_best().map(|ind| ind.fitness());
+        self.observers.generation_start(self.generation + 1);
+        self.generation += 1;
+        let evaluations = self.polish(&memetic);
+        self.record(evaluations, 0, start);
+        self.notify(previous_best)?;
+
+        Ok(())
+    }
+
+    /// Best individual of the current population.
+    fn best(&self) -> Option<Individual> {
+        self.population.get_best().cloned()
+    }
+
+    fn statistics(&self) -> &[GenerationStats] {
+        &self.history
+    }
+
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    fn genomes(&self) -> Vec<Genome> {
+        self.population
+            .individuals()
+            .iter()
+            .map(|ind| ind.genome())
+            .collect()
+    }
+
+    fn observers(&mut self) -> &mut Observers {
+        &mut self.observers
+    }
 
-        self.population.get_best().map(|ind| ind.clone())
+    fn save_checkpoint(&self) -> io::Result<()> {
+        GeneticAlgorithm::save_checkpoint(self)
     }
 }
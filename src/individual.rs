@@ -1,29 +1,127 @@
 use aule::prelude::*;
-use rand::{Rng, rngs::StdRng};
-use std::f32::consts::PI;
-
-use crate::input;
-
-#[derive(Clone, Copy, Default)]
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, sync::Arc};
+
+use crate::{
+    cache::FitnessCache,
+    metric::{ErrorMetric, Metric},
+    scenario::{Disturbance, Objective, WeightedScenario},
+    trace::{TraceOptions, TraceSample, TraceWriter},
+    work::stream_seed,
+};
+
+/// Plant controlled by the PID; see also [`Self::BENCHMARKS`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Model {
     #[default]
+    #[serde(rename = "dc_motor")]
     DCMotor,
+    #[serde(rename = "complex")]
     Complex,
+    /// 1 / (s + 1)^4.
+    #[serde(rename = "high_order_lag")]
+    HighOrderLag,
+    /// Lags a factor 2 apart, 1 / ((s + 1)(0.5 s + 1)(0.25 s + 1)(0.125 s + 1)).
+    #[serde(rename = "multiple_lag")]
+    MultipleLag,
+    /// Right half-plane zero, (1 - s) / (s + 1)^3.
+    #[serde(rename = "non_minimum_phase")]
+    NonMinimumPhase,
+    /// 1 / (s (s + 1)^2).
+    #[serde(rename = "integrating")]
+    Integrating,
+    /// Lightly damped poles, 1 / ((s + 1)(s^2 + 0.2 s + 1)).
+    #[serde(rename = "oscillatory")]
+    Oscillatory,
+    /// Dead time twice the time constant, e^(-s) / (0.5 s + 1).
+    #[serde(rename = "dead_time")]
+    DeadTime,
+}
+
+impl Model {
+    /// Standard benchmark plants, one per class of dynamics.
+    pub const BENCHMARKS: [Model; 6] = [
+        Model::HighOrderLag,
+        Model::MultipleLag,
+        Model::NonMinimumPhase,
+        Model::Integrating,
+        Model::Oscillatory,
+        Model::DeadTime,
+    ];
+
+    /// Name used in configuration files and reports.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Model::DCMotor => "dc_motor",
+            Model::Complex => "complex",
+            Model::HighOrderLag => "high_order_lag",
+            Model::MultipleLag => "multiple_lag",
+            Model::NonMinimumPhase => "non_minimum_phase",
+            Model::Integrating => "integrating",
+            Model::Oscillatory => "oscillatory",
+            Model::DeadTime => "dead_time",
+        }
+    }
+
+    /// Numerator and denominator, highest power of s first, without dead time.
+    pub fn transfer_function(&self) -> (&'static [f32], &'static [f32]) {
+        match self {
+            Model::DCMotor => (&[1.0], &[1.0, 1.0]),
+            Model::Complex => (&[-0.3183, 1.0], &[1.013e-1, 0.0318, 1.0]),
+            Model::HighOrderLag => (&[1.0], &[1.0, 4.0, 6.0, 4.0, 1.0]),
+            Model::MultipleLag => (&[1.0], &[0.015625, 0.234375, 1.09375, 1.875, 1.0]),
+            Model::NonMinimumPhase => (&[-1.0, 1.0], &[1.0, 3.0, 3.0, 1.0]),
+            Model::Integrating => (&[1.0], &[1.0, 2.0, 1.0, 0.0]),
+            Model::Oscillatory => (&[1.0], &[1.0, 1.2, 1.2, 1.0]),
+            Model::DeadTime => (&[1.0], &[0.5, 1.0]),
+        }
+    }
+
+    /// Seconds between a change of the plant input and its first effect.
+    pub fn dead_time(&self) -> f32 {
+        match self {
+            Model::DeadTime => 1.0,
+            _ => 0.0,
+        }
+    }
 }
 
+/// PID gains, the genes of an [`Individual`].
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Genome {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+}
+
+/// Every error metric of one scenario.
+pub struct ScenarioMetrics {
+    pub name: String,
+    pub weight: f32,
+    pub values: [f32; 4],
+}
+
+impl ScenarioMetrics {
+    pub fn value(&self, metric: Metric) -> f32 {
+        self.values[metric as usize]
+    }
+}
+
+/// A genome with its fitness over an [`Objective`]; lower is better.
 #[derive(Clone)]
 pub struct Individual {
     kp: f32,
     ki: f32,
     kd: f32,
     fitness: f32,
-    model: Model,
-    dir: &'static str,
+    objective: Arc<Objective>,
+    dir: Arc<str>,
     seed: u64,
 }
 
 // father: 0.123124  mother: 0.567890 digit: 2 and random = father -> (0.003000, 0.007000)
-fn crossover_digit(father: f32, mother: f32, digit: i32, rng: &mut StdRng) -> (f32, f32) {
+fn crossover_digit(father: f32, mother: f32, digit: i32, rng: &mut impl Rng) -> (f32, f32) {
     let factor = 10f32.powi(digit);
     let father_digit = ((father / factor) as u32 % 10) as f32;
     let mother_digit = ((mother / factor) as u32 % 10) as f32;
@@ -37,7 +135,7 @@ fn crossover_digit(father: f32, mother: f32, digit: i32, rng: &mut StdRng) -> (f
     (d1 * factor, d2 * factor)
 }
 
-fn crossover_float(father: f32, mother: f32, range: (i32, i32), rng: &mut StdRng) -> (f32, f32) {
+fn crossover_float(father: f32, mother: f32, range: (i32, i32), rng: &mut impl Rng) -> (f32, f32) {
     let mut child1 = 0.0;
     let mut child2 = 0.0;
 
@@ -50,36 +148,37 @@ fn crossover_float(father: f32, mother: f32, range: (i32, i32), rng: &mut StdRng
     (child1, child2)
 }
 
-impl Individual {
-    pub fn new(kp: f32, ki: f32, kd: f32, model: Model, dir: &'static str, seed: u64) -> Self {
-        Self {
-            kp,
-            ki,
-            kd,
-            fitness: Self::eval_fitness(kp, ki, kd, false, model, dir, seed),
-            model,
-            dir,
-            seed,
-        }
+impl Genome {
+    pub fn new(kp: f32, ki: f32, kd: f32) -> Self {
+        Self { kp, ki, kd }
     }
 
+    /// Clamps each gain to `[0, bound]` for bounds `[max_kp, max_ki, max_kd]`.
+    pub fn clamp(self, bounds: [f32; 3]) -> Genome {
+        let [max_kp, max_ki, max_kd] = bounds;
+        Genome::new(
+            self.kp.clamp(0.0, max_kp),
+            self.ki.clamp(0.0, max_ki),
+            self.kd.clamp(0.0, max_kd),
+        )
+    }
+
+    /// Two children taking each digit in `digit_range` from either parent.
     pub fn crossover(
         &self,
-        other: &Individual,
+        other: &Genome,
         digit_range: (i32, i32),
-        rng: &mut StdRng,
-    ) -> Vec<Individual> {
+        rng: &mut impl Rng,
+    ) -> Vec<Genome> {
         let (kp1, kp2) = crossover_float(self.kp, other.kp, digit_range, rng);
         let (ki1, ki2) = crossover_float(self.ki, other.ki, digit_range, rng);
         let (kd1, kd2) = crossover_float(self.kd, other.kd, digit_range, rng);
 
-        vec![
-            Individual::new(kp1, ki1, kd1, self.model, self.dir, self.seed),
-            Individual::new(kp2, ki2, kd2, self.model, self.dir, self.seed),
-        ]
+        vec![Genome::new(kp1, ki1, kd1), Genome::new(kp2, ki2, kd2)]
     }
 
-    pub fn mutate(self, mutation_rate: f32, mutation_step: f32, rng: &mut StdRng) -> Individual {
+    /// Adds a uniform step of up to `mutation_step` to each gain, at least zero.
+    pub fn mutate(self, mutation_rate: f32, mutation_step: f32, rng: &mut impl Rng) -> Genome {
         let kp = self.kp
             + if rng.random::<f32>() < mutation_rate {
                 lerp(rng.random::<f32>(), -mutation_step, mutation_step)
@@ -101,69 +200,180 @@ impl Individual {
                 0.0
             };
 
-        Individual::new(
-            kp.max(0.0),
-            ki.max(0.0),
-            kd.max(0.0),
-            self.model,
-            self.dir,
-            self.seed,
-        )
+        Genome::new(kp.max(0.0), ki.max(0.0), kd.max(0.0))
+    }
+}
+
+impl Individual {
+    /// Scores `genome`, reusing the cached fitness when there is one.
+    pub fn from_genome(
+        genome: Genome,
+        objective: Arc<Objective>,
+        dir: Arc<str>,
+        seed: u64,
+        cache: &FitnessCache,
+    ) -> Self {
+        if let Some(fitness) = objective.screening.screen(objective.model, genome) {
+            cache.count_screened(fitness.is_infinite());
+            return Self::with_fitness(genome, fitness, objective, dir, seed);
+        }
+
+        let fitness = cache.fitness(genome, &objective, objective.evaluation_seed(seed), || {
+            Self::eval_fitness(
+                genome.kp, genome.ki, genome.kd, false, &objective, &dir, seed,
+            )
+        });
+
+        Self::with_fitness(genome, fitness, objective, dir, seed)
+    }
+
+    /// Wraps a genome whose fitness is already known.
+    pub fn with_fitness(
+        genome: Genome,
+        fitness: f32,
+        objective: Arc<Objective>,
+        dir: Arc<str>,
+        seed: u64,
+    ) -> Self {
+        Self {
+            kp: genome.kp,
+            ki: genome.ki,
+            kd: genome.kd,
+            fitness,
+            objective,
+            dir,
+            seed,
+        }
     }
 
-    pub fn show(&self) {
-        Self::eval_fitness(
-            self.kp, self.ki, self.kd, true, self.model, self.dir, self.seed,
+    pub fn genome(&self) -> Genome {
+        Genome::new(self.kp, self.ki, self.kd)
+    }
+
+    /// Writes the trace CSVs of every scenario for this individual.
+    pub fn show(&self, options: &TraceOptions) {
+        Self::write_traces(
+            self.kp,
+            self.ki,
+            self.kd,
+            &self.objective,
+            &self.dir,
+            self.seed,
+            options,
         );
     }
 
+    /// Weighted error of the gains, writing traces when `plotter_en`.
     pub fn eval_fitness(
         kp: f32,
         ki: f32,
         kd: f32,
         plotter_en: bool,
-        model: Model,
+        objective: &Objective,
         dir: &str,
         seed: u64,
     ) -> f32 {
-        let time = Time::continuous(1e-2, 8.0 * PI);
-
-        let inputs: [(&str, Box<InputBlock>); _] = [
-            ("step", Box::new(input::Step::new(1.0))),
-            (
-                "sinusoidal",
-                Box::new(input::Sinusoidal::new(2.0 * PI, 1.0, 0.0)),
-            ),
-            ("square", Box::new(input::Square::new(2.0 * PI, 1.0, 0.0))),
-            (
-                "sawtooth",
-                Box::new(input::Sawtooth::new(2.0 * PI, 1.0, 0.0)),
-            ),
-            (
-                "random",
-                Box::new(input::Random::new(0.0, 1.0, 2.0 * PI, 2.5 * PI, seed)),
-            ),
-        ];
-        let mut sims = inputs.map(|(name, input)| {
-            let name = if plotter_en {
-                Some(name.to_string())
-            } else {
-                None
-            };
-            Simulation::new(kp, ki, kd, input, name, model, dir)
-        });
+        let options = TraceOptions::default();
+        let trace = if plotter_en {
+            Some((dir, &options))
+        } else {
+            None
+        };
+
+        Self::traced_fitness(kp, ki, kd, objective, trace, seed)
+    }
+
+    /// Scores the gains while writing every scenario's trace to `output/<dir>`.
+    pub fn write_traces(
+        kp: f32,
+        ki: f32,
+        kd: f32,
+        objective: &Objective,
+        dir: &str,
+        seed: u64,
+        options: &TraceOptions,
+    ) -> f32 {
+        Self::traced_fitness(kp, ki, kd, objective, Some((dir, options)), seed)
+    }
+
+    fn traced_fitness(
+        kp: f32,
+        ki: f32,
+        kd: f32,
+        objective: &Objective,
+        trace: Option<(&str, &TraceOptions)>,
+        seed: u64,
+    ) -> f32 {
+        let include = |scenario: &WeightedScenario| trace.is_some() || scenario.weight > 0.0;
+
+        Self::simulate(kp, ki, kd, include, objective, trace, seed)
+            .iter()
+            .filter(|(scenario, _)| scenario.weight > 0.0)
+            .map(|(scenario, sim)| scenario.weight * sim.error_metric.value())
+            .sum()
+    }
+
+    /// All error metrics of every scenario, weighted or not.
+    pub fn eval_metrics(
+        kp: f32,
+        ki: f32,
+        kd: f32,
+        objective: &Objective,
+        seed: u64,
+    ) -> Vec<ScenarioMetrics> {
+        Self::simulate(kp, ki, kd, |_| true, objective, None, seed)
+            .into_iter()
+            .map(|(scenario, sim)| ScenarioMetrics {
+                name: scenario.name().to_string(),
+                weight: scenario.weight,
+                values: Metric::ALL.map(|metric| sim.error_metric.value_of(metric)),
+            })
+            .collect()
+    }
+
+    fn simulate<'a>(
+        kp: f32,
+        ki: f32,
+        kd: f32,
+        include: impl Fn(&WeightedScenario) -> bool,
+        objective: &'a Objective,
+        trace: Option<(&str, &TraceOptions)>,
+        seed: u64,
+    ) -> Vec<(&'a WeightedScenario, Simulation)> {
+        let time = Time::continuous(objective.dt, objective.duration);
+        let seed = objective.evaluation_seed(seed);
+
+        let mut sims = objective
+            .scenarios
+            .iter()
+            .enumerate()
+            .filter(|(_, scenario)| include(scenario))
+            .map(|(index, scenario)| {
+                let writer = trace.and_then(|(dir, options)| {
+                    let path = format!("output/{}/{}.csv", dir, scenario.name());
+                    let genome = Genome::new(kp, ki, kd);
+                    TraceWriter::create(path.as_ref(), scenario.name(), genome, objective, *options)
+                        .inspect_err(|err| log::error!("Error creating trace {path}: {err}"))
+                        .ok()
+                });
+                let sim = Simulation::new(
+                    Genome::new(kp, ki, kd),
+                    scenario.scenario.block(seed),
+                    writer,
+                    objective,
+                    stream_seed(seed, index),
+                );
+                (scenario, sim)
+            })
+            .collect::<Vec<_>>();
 
         for dt in time {
-            if plotter_en {
-                for sim in sims.iter_mut() {
-                    let _ = dt * sim.as_block();
-                }
-            } else {
-                let _ = dt * sims[2].as_block();
+            for (_, sim) in sims.iter_mut() {
+                let _ = dt * sim.as_block();
             }
         }
 
-        sims[2].error_metric_value()
+        sims
     }
 
     pub fn kp(&self) -> f32 {
@@ -199,48 +409,62 @@ impl PartialOrd for Individual {
     }
 }
 
-type InputBlock = dyn Block<Input = (), Output = f32, TimeType = Continuous>;
+/// Reference signal fed to the closed loop.
+pub type InputBlock = dyn Block<Input = (), Output = f32, TimeType = Continuous>;
 
 struct Simulation {
     input: Box<InputBlock>,
-    error_metric: IAE<Continuous>,
+    error_metric: ErrorMetric,
     pid: PID<Continuous>,
+    // One controller per term, only run when a detailed trace is written.
+    terms: Option<[PID<Continuous>; 3]>,
     plant: SS<Euler>,
-    writter: Option<Writter<2, Continuous>>,
+    // Control signals still in transit through the plant's dead time.
+    dead_time: Option<VecDeque<f32>>,
+    disturbance: Option<Disturbance>,
+    noise: Option<(f32, StdRng)>,
+    trace: Option<TraceWriter>,
+}
+
+// Standard normal sample by the Box-Muller transform.
+pub(crate) fn gaussian(rng: &mut impl Rng) -> f32 {
+    let u1 = 1.0 - rng.random::<f32>();
+    let u2 = rng.random::<f32>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos()
 }
 
 impl Simulation {
     pub fn new(
-        kp: f32,
-        ki: f32,
-        kd: f32,
+        genome: Genome,
         input: Box<InputBlock>,
-        name: Option<String>,
-        model: Model,
-        dir: &str,
+        trace: Option<TraceWriter>,
+        objective: &Objective,
+        noise_seed: u64,
     ) -> Self {
-        let k = 1.0;
-        let a = 1.0;
-        let tf_motor = Tf::new(&[k], &[1.0, k * a]).into();
-        let tf_complex = Tf::new(&[-0.3183, 1.0], &[1.013e-1, 0.0318, 1.0]).into();
+        let (numerator, denominator) = objective.model.transfer_function();
+        let Genome { kp, ki, kd } = genome;
 
         Self {
             input,
-            error_metric: IAE::default(),
+            error_metric: ErrorMetric::new(objective.metric),
             pid: PID::new(kp, ki, kd),
-            plant: match model {
-                Model::DCMotor => tf_motor,
-                Model::Complex => tf_complex,
-            },
-            writter: name.map(|name| {
-                Writter::new(&format!("output/{}/{}.csv", dir, name), ["input", "output"])
+            terms: trace.as_ref().filter(|trace| trace.detailed()).map(|_| {
+                [
+                    PID::new(kp, 0.0, 0.0),
+                    PID::new(0.0, ki, 0.0),
+                    PID::new(0.0, 0.0, kd),
+                ]
             }),
+            plant: Tf::new(numerator, denominator).into(),
+            dead_time: Some((objective.model.dead_time() / objective.dt).round() as usize)
+                .filter(|&steps| steps > 0)
+                .map(|steps| VecDeque::from(vec![0.0; steps])),
+            disturbance: objective.disturbance,
+            noise: (objective.noise > 0.0)
+                .then(|| (objective.noise, StdRng::seed_from_u64(noise_seed))),
+            trace,
         }
     }
-
-    pub fn error_metric_value(&self) -> f32 {
-        self.error_metric.value()
-    }
 }
 
 impl Block for Simulation {
@@ -252,15 +476,49 @@ impl Block for Simulation {
         &mut self,
         input: Signal<Self::Input, Self::TimeType>,
     ) -> Signal<Self::Output, Self::TimeType> {
+        let t = input.delta.sim_time().as_secs_f32();
         let signal = self.input.output(input);
-        let error = signal - self.plant.last_output();
+        let noise = self
+            .noise
+            .as_mut()
+            .map_or(0.0, |(std_dev, rng)| *std_dev * gaussian(rng));
+        let disturbance = self
+            .disturbance
+            .map_or(0.0, |disturbance| disturbance.at(t));
+
+        // The metric scores the true error, not the noisy measurement.
+        let tracking_error = signal - self.plant.last_output();
+        let error = tracking_error - noise;
         let control_signal = error * self.pid.as_block();
-        let output = control_signal * self.plant.as_block();
-
-        let _ = error * self.error_metric.as_block();
-
-        if let Some(writter) = &mut self.writter {
-            let _ = signal.map(|s| [s, output.value]) * writter.as_block();
+        let delayed = match &mut self.dead_time {
+            Some(line) => control_signal.map(|u| {
+                line.push_back(u);
+                line.pop_front().unwrap_or(0.0)
+            }),
+            None => control_signal,
+        };
+        let output = delayed.map(|u| u + disturbance) * self.plant.as_block();
+
+        let _ = tracking_error * self.error_metric.as_block();
+
+        if let Some(trace) = &mut self.trace {
+            let terms = self.terms.as_mut().map_or([0.0; 3], |terms| {
+                terms.each_mut().map(|pid| (error * pid.as_block()).value)
+            });
+            let sample = TraceSample {
+                t,
+                input: signal.value,
+                output: output.value,
+                control: control_signal.value,
+                error: error.value,
+                terms,
+                disturbance,
+                noise,
+            };
+            if let Err(err) = trace.write(&sample) {
+                log::error!("Error writing trace: {err}");
+                self.trace = None;
+            }
         }
 
         output
@@ -2,6 +2,7 @@ use aule::prelude::{Block, Continuous, Signal};
 use rand::{Rng, SeedableRng, rngs::StdRng};
 use std::{f32::consts::PI, time::Duration};
 
+/// Constant reference of `amplitude`.
 pub struct Step {
     amplitude: f32,
 }
@@ -25,6 +26,7 @@ impl Block for Step {
     }
 }
 
+/// `offset + amplitude * sin(2πt / period)`.
 pub struct Sinusoidal {
     period: f32,
     amplitude: f32,
@@ -56,6 +58,7 @@ impl Block for Sinusoidal {
     }
 }
 
+/// Alternates between `offset + amplitude` and `offset` every half period.
 pub struct Square {
     period: f32,
     amplitude: f32,
@@ -94,6 +97,7 @@ impl Block for Square {
     }
 }
 
+/// Ramps from `offset` to `offset + amplitude` over each period.
 pub struct Sawtooth {
     period: f32,
     amplitude: f32,
@@ -128,6 +132,7 @@ impl Block for Sawtooth {
     }
 }
 
+/// Holds a random amplitude for a random period, then draws new ones.
 pub struct Random {
     max_amplitude: f32,
     min_amplitude: f32,
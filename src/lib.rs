@@ -0,0 +1,70 @@
+//! PID tuning by genetic algorithm and other population-based optimisers.
+//!
+//! Every search strategy implements [`Optimizer`], scoring [`Individual`]s on an [`Objective`].
+//!
+//! ```no_run
+//! use pid_opt::{GeneticAlgorithmBuilder, Model, Objective, Optimizer};
+//!
+//! let mut ga = GeneticAlgorithmBuilder::default()
+//!     .with_objective(Objective {
+//!         model: Model::DCMotor,
+//!         ..Objective::default()
+//!     })
+//!     .with_population_size(200)
+//!     .with_parallel_works(4)
+//!     .with_mutation_step(1.0)
+//!     .with_digit_range((-1, 3))
+//!     .with_max_kp(100.0)
+//!     .with_max_ki(100.0)
+//!     .with_max_kd(0.0)
+//!     .with_output_dir("example")
+//!     .with_seed(42)
+//!     .build()?;
+//!
+//! for _ in 0..20 {
+//!     ga.eval(0.75, 0.3)?;
+//! }
+//! let best = ga.best().expect("a built GA is never empty");
+//! println!("kp {} ki {} kd {} -> {}", best.kp(), best.ki(), best.kd(), best.fitness());
+//! # Ok::<(), pid_opt::OptimizerError>(())
+//! ```
+
+pub mod cache;
+mod checkpoint;
+pub mod cma_es;
+pub mod differential_evolution;
+pub mod experiment;
+pub mod genetic_algorithm;
+pub mod individual;
+pub mod input;
+pub mod local_search;
+pub mod metric;
+pub mod multi_seed;
+pub mod observer;
+pub mod optimizer;
+pub mod population;
+pub mod pso;
+pub mod sampling;
+pub mod scenario;
+pub mod stability;
+pub mod stats;
+mod trace;
+pub mod tuning;
+pub mod work;
+
+pub use cache::FitnessCache;
+pub use cma_es::{CmaEs, CmaEsBuilder};
+pub use differential_evolution::{DifferentialEvolution, DifferentialEvolutionBuilder};
+pub use genetic_algorithm::{GeneticAlgorithm, GeneticAlgorithmBuilder};
+pub use individual::{Genome, Individual, InputBlock, Model};
+pub use local_search::{LocalSearch, Memetic};
+pub use metric::Metric;
+pub use observer::{Observer, Termination};
+pub use optimizer::{Optimizer, OptimizerError};
+pub use population::Seeding;
+pub use pso::{ParticleSwarm, ParticleSwarmBuilder};
+pub use sampling::Sampling;
+pub use scenario::{Objective, Scenario, WeightedScenario};
+pub use trace::TraceOptions;
+pub use tuning::{Baseline, Tuning};
+pub use work::{Work, work_pool, work_serial};
@@ -0,0 +1,386 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cache::FitnessCache,
+    individual::{Genome, Individual},
+    scenario::Objective,
+    work::Work,
+};
+
+/// Derivative-free local search used to polish elite individuals.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalSearch {
+    /// Downhill simplex with the standard coefficients.
+    #[default]
+    NelderMead,
+    /// Pattern search, halving its steps when stuck.
+    HookeJeeves,
+}
+
+/// When and how a GA polishes its best individuals with a [`LocalSearch`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Memetic {
+    #[serde(default)]
+    pub method: LocalSearch,
+    /// Polishes every `every` generations; 0 only polishes at the end.
+    #[serde(default)]
+    pub every: usize,
+    /// Polishes once more when the run completes.
+    #[serde(default = "default_at_end")]
+    pub at_end: bool,
+    /// Number of best individuals polished each time.
+    #[serde(default = "default_elites")]
+    pub elites: usize,
+    /// Fitness evaluations allowed per polished individual.
+    #[serde(default = "default_budget")]
+    pub budget: usize,
+    /// Initial step of the search as a fraction of each gain's bound.
+    #[serde(default = "default_step")]
+    pub step: f32,
+}
+
+fn default_at_end() -> bool {
+    true
+}
+
+fn default_elites() -> usize {
+    3
+}
+
+fn default_budget() -> usize {
+    60
+}
+
+fn default_step() -> f32 {
+    0.02
+}
+
+impl Default for Memetic {
+    fn default() -> Self {
+        Self {
+            method: LocalSearch::default(),
+            every: 0,
+            at_end: default_at_end(),
+            elites: default_elites(),
+            budget: default_budget(),
+            step: default_step(),
+        }
+    }
+}
+
+impl Memetic {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.elites == 0 {
+            return Err("elites must be at least 1".to_string());
+        }
+        if self.budget == 0 {
+            return Err("budget must be at least 1".to_string());
+        }
+        if !(self.step.is_finite() && self.step > 0.0) {
+            return Err(format!("step must be a positive number, got {}", self.step));
+        }
+
+        Ok(())
+    }
+}
+
+type Point = [f32; 3];
+
+// Cost function that stops answering once its budget is spent.
+struct Budgeted<F> {
+    cost: F,
+    left: usize,
+    used: usize,
+}
+
+impl<F: FnMut(Point) -> f32> Budgeted<F> {
+    fn eval(&mut self, x: Point) -> Option<(Point, f32)> {
+        if self.left == 0 {
+            return None;
+        }
+        self.left -= 1;
+        self.used += 1;
+
+        let x = x.map(|gain| gain.max(0.0));
+        let cost = (self.cost)(x);
+        Some((
+            x,
+            if cost.is_finite() {
+                cost
+            } else {
+                f32::INFINITY
+            },
+        ))
+    }
+}
+
+fn lerp(from: Point, to: Point, t: f32) -> Point {
+    std::array::from_fn(|d| from[d] + t * (to[d] - from[d]))
+}
+
+fn nelder_mead<F: FnMut(Point) -> f32>(
+    start: (Point, f32),
+    steps: Point,
+    cost: &mut Budgeted<F>,
+) -> (Point, f32) {
+    let mut simplex = vec![start];
+    for d in (0..3).filter(|&d| steps[d] > 0.0) {
+        let mut x = start.0;
+        x[d] += steps[d];
+        match cost.eval(x) {
+            Some(vertex) => simplex.push(vertex),
+            None => return start,
+        }
+    }
+
+    let n = simplex.len() - 1;
+    if n == 0 {
+        return start;
+    }
+    loop {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let (best, worst) = (simplex[0], simplex[n]);
+        if worst.1 - best.1 <= 1e-7 * best.1.abs() {
+            break;
+        }
+
+        let centroid = simplex[..n].iter().fold([0.0; 3], |sum, (x, _)| {
+            std::array::from_fn(|d| sum[d] + x[d] / n as f32)
+        });
+        let Some(reflected) = cost.eval(lerp(centroid, worst.0, -1.0)) else {
+            break;
+        };
+
+        if reflected.1 < best.1 {
+            let Some(expanded) = cost.eval(lerp(centroid, worst.0, -2.0)) else {
+                simplex[n] = reflected;
+                break;
+            };
+            simplex[n] = if expanded.1 < reflected.1 {
+                expanded
+            } else {
+                reflected
+            };
+        } else if reflected.1 < simplex[n - 1].1 {
+            simplex[n] = reflected;
+        } else {
+            let outside = reflected.1 < worst.1;
+            let towards = if outside { reflected.0 } else { worst.0 };
+            let Some(contracted) = cost.eval(lerp(centroid, towards, 0.5)) else {
+                break;
+            };
+            if contracted.1 < reflected.1.min(worst.1) {
+                simplex[n] = contracted;
+            } else {
+                for vertex in simplex.iter_mut().skip(1) {
+                    match cost.eval(lerp(best.0, vertex.0, 0.5)) {
+                        Some(shrunk) => *vertex = shrunk,
+                        None => break,
+                    }
+                }
+            }
+        }
+    }
+
+    simplex
+        .into_iter()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or(start)
+}
+
+// Tries a step up then down along each gain, keeping whatever improves.
+fn explore<F: FnMut(Point) -> f32>(
+    mut current: (Point, f32),
+    steps: Point,
+    cost: &mut Budgeted<F>,
+) -> (Point, f32) {
+    for d in (0..3).filter(|&d| steps[d] > 0.0) {
+        for sign in [1.0, -1.0] {
+            let mut x = current.0;
+            x[d] += sign * steps[d];
+            let Some(candidate) = cost.eval(x) else {
+                return current;
+            };
+            if candidate.1 < current.1 {
+                current = candidate;
+                break;
+            }
+        }
+    }
+
+    current
+}
+
+fn hooke_jeeves<F: FnMut(Point) -> f32>(
+    start: (Point, f32),
+    steps: Point,
+    cost: &mut Budgeted<F>,
+) -> (Point, f32) {
+    let mut base = start;
+    let mut h = steps;
+    while cost.left > 0 {
+        let mut moved = explore(base, h, cost);
+        if moved.1 < base.1 {
+            while moved.1 < base.1 {
+                let previous = base;
+                base = moved;
+                let Some(pattern) = cost.eval(lerp(previous.0, base.0, 2.0)) else {
+                    break;
+                };
+                moved = explore(pattern, h, cost);
+            }
+        } else {
+            h = h.map(|step| step / 2.0);
+            if (0..3).all(|d| h[d] <= steps[d] * 1e-4) {
+                break;
+            }
+        }
+    }
+
+    base
+}
+
+/// Minimises `cost` from `start` with at most `budget` evaluations.
+pub fn minimise(
+    method: LocalSearch,
+    start: (Point, f32),
+    steps: Point,
+    budget: usize,
+    cost: impl FnMut(Point) -> f32,
+) -> (Point, f32, usize) {
+    let mut cost = Budgeted {
+        cost,
+        left: budget,
+        used: 0,
+    };
+    let (point, value) = match method {
+        LocalSearch::NelderMead => nelder_mead(start, steps, &mut cost),
+        LocalSearch::HookeJeeves => hooke_jeeves(start, steps, &mut cost),
+    };
+
+    (point, value, cost.used)
+}
+
+/// Polishes individuals, returning each with the evaluations it took.
+#[derive(Clone)]
+pub(crate) struct Polish {
+    pub memetic: Memetic,
+    pub bounds: [f32; 3],
+    pub objective: Arc<Objective>,
+    pub dir: Arc<str>,
+    pub seed: u64,
+    pub cache: FitnessCache,
+}
+
+impl Work for Polish {
+    type Input = Individual;
+    type Output = (Individual, usize);
+
+    fn work(&mut self, input: Vec<Self::Input>) -> Vec<Self::Output> {
+        input
+            .into_iter()
+            .map(|ind| {
+                let genome = ind.genome();
+                let start = [genome.kp, genome.ki, genome.kd];
+                let steps = self.bounds.map(|bound| self.memetic.step * bound);
+                let (point, fitness, evaluations) = minimise(
+                    self.memetic.method,
+                    (start, ind.fitness()),
+                    steps,
+                    self.memetic.budget,
+                    |[kp, ki, kd]| {
+                        Individual::from_genome(
+                            Genome::new(kp, ki, kd),
+                            self.objective.clone(),
+                            self.dir.clone(),
+                            self.seed,
+                            &self.cache,
+                        )
+                        .fitness()
+                    },
+                );
+
+                let [kp, ki, kd] = point;
+                let polished = Individual::with_fitness(
+                    Genome::new(kp, ki, kd),
+                    fitness,
+                    self.objective.clone(),
+                    self.dir.clone(),
+                    self.seed,
+                );
+                (polished, evaluations)
+            })
+            .collect()
+    }
+
+    fn set_start(&mut self, _start: usize) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METHODS: [LocalSearch; 2] = [LocalSearch::NelderMead, LocalSearch::HookeJeeves];
+    const MINIMUM: Point = [1.0, 2.0, 0.5];
+
+    fn bowl(x: Point) -> f32 {
+        (0..3).map(|d| (x[d] - MINIMUM[d]).powi(2)).sum()
+    }
+
+    #[test]
+    fn converges_on_a_bowl() {
+        for method in METHODS {
+            let start = [0.0; 3];
+            let (point, value, used) = minimise(method, (start, bowl(start)), [0.5; 3], 500, bowl);
+            assert!(value < 1e-4, "{method:?}: {point:?} at {value}");
+            assert_eq!(value, bowl(point), "{method:?}");
+            assert!(used <= 500, "{method:?}");
+        }
+    }
+
+    #[test]
+    fn respects_the_budget_and_never_worsens() {
+        for method in METHODS {
+            for budget in [0, 1, 2, 7] {
+                let start = [3.0, 0.0, 1.0];
+                let mut calls = 0;
+                let (_, value, used) =
+                    minimise(method, (start, bowl(start)), [0.5; 3], budget, |x| {
+                        calls += 1;
+                        bowl(x)
+                    });
+                assert_eq!(used, calls, "{method:?}");
+                assert!(used <= budget, "{method:?}");
+                assert!(value <= bowl(start), "{method:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_gains_non_negative_and_fixed_without_a_step() {
+        let cost = |x: Point| (x[0] + 1.0).powi(2) + (x[1] - 1.0).powi(2) + x[2];
+        for method in METHODS {
+            let start = [0.5, 0.0, 0.3];
+            let (point, _, _) = minimise(method, (start, cost(start)), [0.2, 0.2, 0.0], 200, cost);
+            assert_eq!(point[0], 0.0, "{method:?}");
+            assert_eq!(point[2], start[2], "{method:?}");
+        }
+    }
+
+    #[test]
+    fn non_finite_costs_lose() {
+        for method in METHODS {
+            let cost = |x: Point| if x[0] > 1.0 { f32::NAN } else { bowl(x) };
+            let start = [0.0, 2.0, 0.5];
+            let (point, value, _) = minimise(method, (start, cost(start)), [0.5; 3], 200, cost);
+            assert!(
+                point[0] <= 1.0 && value.is_finite(),
+                "{method:?}: {point:?}"
+            );
+        }
+    }
+}
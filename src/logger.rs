@@ -0,0 +1,85 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    sync::{LazyLock, Mutex},
+    time::Instant,
+};
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+// Writes every record to stderr and to the log file of the current run.
+struct Logger {
+    start: Instant,
+    file: Mutex<Option<File>>,
+}
+
+static LOGGER: LazyLock<Logger> = LazyLock::new(|| Logger {
+    start: Instant::now(),
+    file: Mutex::new(None),
+});
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "[{:>10.3} {:<5}] {}\n",
+            self.start.elapsed().as_secs_f64(),
+            record.level(),
+            record.args()
+        );
+        let _ = io::stderr().write_all(line.as_bytes());
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let _ = file.flush();
+        }
+    }
+}
+
+pub fn init(level: LevelFilter) {
+    log::set_logger(&*LOGGER).expect("logger already initialised");
+    log::set_max_level(level);
+}
+
+// Log file of the run holding it, closed when dropped.
+pub struct RunLog(());
+
+impl Drop for RunLog {
+    fn drop(&mut self) {
+        LOGGER.flush();
+        *LOGGER.file.lock().unwrap() = None;
+    }
+}
+
+// Mirrors the log into `path` while the returned handle lives; one run at a time.
+pub fn log_to_file(path: &Path, append: bool) -> io::Result<RunLog> {
+    let mut current = LOGGER.file.lock().unwrap();
+    if current.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::ResourceBusy,
+            "another run is already logging to a file",
+        ));
+    }
+    *current = Some(
+        OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)?,
+    );
+
+    Ok(RunLog(()))
+}
@@ -1,113 +1,436 @@
-use std::{fs::File, process::Command};
+use std::{
+    fs::{File, OpenOptions},
+    ops::ControlFlow,
+    path::Path,
+    process,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 
 use crate::{
-    genetic_algorithm::{GeneticAlgorithm, GeneticAlgorithmBuilder},
-    individual::Model,
+    cli::{Cli, Command},
+    report::Report,
+    run_dir::RunDir,
+};
+use clap::Parser;
+use pid_opt::{
+    FitnessCache, GeneticAlgorithm, Individual, Observer, Optimizer, OptimizerError, Termination,
+    TraceOptions,
+    experiment::{Algorithm, Experiment, ExperimentError, SeedsConfig},
+    multi_seed::{self, Summary},
+    observer::{LogObserver, MetricsObserver},
+    stats::GenerationStats,
+    tuning,
 };
-use aule::prelude::*;
-use gag::Redirect;
 
-mod genetic_algorithm;
-mod individual;
-mod input;
-mod population;
-mod work;
+mod cli;
+mod logger;
+mod plot;
+mod report;
+mod run_dir;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
 
 fn main() {
-    run_ga(
-        "dc_motor",
-        GeneticAlgorithmBuilder::default()
-            .with_population_size(1_000)
-            .with_parallel_works(4)
-            .with_model(Model::DCMotor)
-            .with_mutation_step(1.0)
-            .with_digit_range((-1, 3))
-            .with_output_dir("dc_motor")
-            .with_max_kp(100.0)
-            .with_max_ki(100.0)
-            .with_seed(0x2268a378740265f9)
-            .build(),
-        100,
-    );
-    run_ga(
-        "complex_system",
-        GeneticAlgorithmBuilder::default()
-            .with_population_size(1_000)
-            .with_parallel_works(4)
-            .with_model(Model::Complex)
-            .with_mutation_step(0.1)
-            .with_digit_range((-10, -1))
-            .with_output_dir("complex_system")
-            .with_max_kp(0.9)
-            .with_max_kd(0.9)
-            .with_seed(0x2268a378740265f9)
-            .build(),
-        100,
-    );
-}
+    let cli = Cli::parse();
+    logger::init(cli.log_level);
 
-fn run_ga(dir: &str, mut ga: GeneticAlgorithm, max_generations: usize) {
-    let dir = format!("output/{dir}");
-    println!("Removing {} dir...", dir);
-    let _ = std::fs::remove_dir_all(&dir);
-    std::fs::create_dir_all(&dir).unwrap();
+    let result = match cli.command {
+        Command::Optimize {
+            configs,
+            overwrite,
+            resume,
+        } => {
+            optimize(configs, overwrite, resume);
+            Ok(())
+        }
+        Command::Evaluate { objective, gains } => cli::evaluate(&objective, &gains),
+        Command::Simulate {
+            objective,
+            gains,
+            output_dir,
+            language,
+            detailed,
+            decimate,
+        } => cli::simulate(
+            &objective,
+            &gains,
+            &output_dir,
+            language,
+            &TraceOptions { detailed, decimate },
+        ),
+        Command::Compare { objective, gains } => cli::compare(&objective, &gains),
+    };
 
-    let file = File::create(format!("{dir}/log.txt")).unwrap();
-    let file_err = File::create(format!("{dir}/err.txt")).unwrap();
-    let _print_gag = Redirect::stdout(file).unwrap();
-    let _print_err_gag = Redirect::stderr(file_err).unwrap();
+    if let Err(err) = result {
+        log::error!("{err}");
+        process::exit(1);
+    }
+}
 
-    println!("Seed: {:#x}", ga.seed());
+fn optimize(mut paths: Vec<String>, overwrite: bool, resume: bool) {
+    ctrlc::set_handler(|| INTERRUPTED.store(true, Ordering::SeqCst)).unwrap();
 
-    println!("Generating initial population...");
+    if paths.is_empty() {
+        paths = default_experiments();
+    }
 
-    let mut best_individual = None;
-    let generations = Time::continuous(1.0, max_generations as f32);
+    let experiments = match paths
+        .iter()
+        .map(|path| Experiment::load(path))
+        .collect::<Result<Vec<_>, ExperimentError>>()
+    {
+        Ok(experiments) => experiments,
+        Err(err) => {
+            log::error!("Invalid experiment: {err}");
+            process::exit(1);
+        }
+    };
 
-    for _ in generations {
-        println!("Evolving generation {}", ga.generation());
-        let Some(best) = ga.eval(0.75, 0.3) else {
-            println!("No best individual found in this generation.");
-            break;
+    // A failing experiment is reported and the next one still runs.
+    for experiment in experiments {
+        let result = match &experiment.seeds {
+            Some(seeds) => run_seeds(&experiment, seeds, overwrite),
+            None => run_experiment(&experiment, overwrite, resume),
         };
+        if let Err(err) = result {
+            log::error!("Cannot run {}: {err}", experiment.output_dir);
+        }
+    }
+}
 
-        best_individual = Some(best.clone());
+fn default_experiments() -> Vec<String> {
+    let mut paths = std::fs::read_dir("experiments")
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    paths.sort();
 
-        println!(
-            "Generation {}:\n  Size: {}\n  Best PID = (kp: {:.10}, ki: {:.10}, kd: {:.10}) with fitness {:.10}",
-            ga.generation(),
-            ga.len(),
-            best.kp(),
-            best.ki(),
-            best.kd(),
-            best.fitness()
+    paths
+}
+
+// Only the GA writes checkpoints, so it is configured here.
+fn build(
+    experiment: &Experiment,
+    dir: Arc<str>,
+    checkpoint: &str,
+    cache: FitnessCache,
+) -> Result<Box<dyn Optimizer>, OptimizerError> {
+    match experiment.optimizer {
+        Algorithm::Ga => Ok(Box::new(
+            experiment
+                .builder()
+                .with_output_dir(dir)
+                .with_checkpoint(checkpoint, experiment.ga.checkpoint_every)
+                .with_fitness_cache(cache)
+                .build()?,
+        )),
+        _ => experiment.optimizer(dir, cache),
+    }
+}
+
+fn run_experiment(experiment: &Experiment, overwrite: bool, resume: bool) -> Result<(), String> {
+    let cache_path = format!("output/cache/{}.json", experiment.output_dir);
+    let cache = FitnessCache::load(&cache_path)
+        .map_err(|err| format!("cannot load fitness cache {cache_path}: {err}"))?;
+    let run = RunDir::prepare(&experiment.output_dir, overwrite, resume)
+        .map_err(|err| format!("cannot prepare run directory: {err}"))?;
+    let dir: Arc<str> = run.name().into();
+    let checkpoint = run.file("checkpoint.json");
+    let resuming = run.resuming();
+
+    let log = run.file("log.txt");
+    let run_log = logger::log_to_file(Path::new(&log), resuming)
+        .map_err(|err| format!("cannot open log {log}: {err}"))?;
+    let saved = run.file("experiment.toml");
+    // Only the GA writes checkpoints, so only a GA run can be resuming.
+    let mut optimizer: Box<dyn Optimizer> = if resuming {
+        if experiment.optimizer != Algorithm::Ga {
+            return Err(format!(
+                "{checkpoint} holds a GA run but the experiment now uses the {}; \
+                 delete it to start over",
+                experiment.optimizer.name()
+            ));
+        }
+        warn_if_changed(experiment, &saved);
+        log::info!("Resuming from {}...", checkpoint);
+        Box::new(
+            GeneticAlgorithm::resume(&checkpoint, cache.clone())
+                .map_err(|err| format!("cannot resume from {checkpoint}: {err}"))?,
+        )
+    } else {
+        log::info!("Writing run to {}", run.path().display());
+        log::info!(
+            "Generating initial population for the {}...",
+            experiment.optimizer.name()
+        );
+        let optimizer = build(experiment, dir.clone(), &checkpoint, cache.clone())
+            .map_err(|err| format!("cannot start: {err}"))?;
+        // A resumed run keeps the configuration it was started with.
+        experiment
+            .save(&saved)
+            .map_err(|err| format!("cannot save {saved}: {err}"))?;
+        optimizer
+    };
+
+    log::info!("Seed: {:#x}", experiment.seed);
+
+    // Rewritten so a resumed run drops generations after its checkpoint.
+    let metrics_path = run.file("metrics.jsonl");
+    let metrics = File::create(&metrics_path)
+        .map_err(|err| format!("cannot create {metrics_path}: {err}"))?;
+    let history = optimizer.statistics().to_vec();
+    optimizer.add_observer(Box::new(MetricsObserver::new(metrics, &history)));
+    optimizer.add_observer(Box::new(LogObserver));
+    optimizer.add_observer(Box::new(InterruptObserver));
+
+    let termination = optimizer.run(experiment.ga.generations);
+    if termination == Ok(Termination::Stopped) && INTERRUPTED.load(Ordering::SeqCst) {
+        log::warn!("Interrupted, saving checkpoint...");
+        if let Err(err) = optimizer.save_checkpoint() {
+            log::error!("Error saving checkpoint: {err}");
+        }
+        if let Err(err) = cache.save() {
+            log::error!("Error saving fitness cache: {err}");
+        }
+        drop(run_log);
+        process::exit(130);
+    }
+    let mut best_individual = optimizer.best();
+    let mut history = optimizer.statistics().to_vec();
+
+    if experiment.cma_es.refine_generations > 0 && experiment.optimizer != Algorithm::CmaEs {
+        let metrics = run.file("metrics.jsonl");
+        let refined = best_individual.as_ref().and_then(|best| {
+            refine(
+                experiment,
+                dir.clone(),
+                cache.clone(),
+                best,
+                &mut history,
+                &metrics,
+            )
+        });
+        if refined.is_some() {
+            best_individual = refined;
+        }
+    }
+
+    let baselines = tuning::baselines(
+        Arc::new(experiment.objective()),
+        experiment.ga.parallel_works,
+        dir,
+        experiment.seed,
+        cache.clone(),
+    );
+    log::info!("Classical tunings:");
+    for baseline in &baselines {
+        let individual = &baseline.individual;
+        log::info!(
+            "{}: PID = (kp: {:.6}, ki: {:.6}, kd: {:.6}) with fitness {:.10}",
+            baseline.tuning.name(),
+            individual.kp(),
+            individual.ki(),
+            individual.kd(),
+            individual.fitness()
         );
     }
 
-    if let Some(best) = best_individual {
-        println!(
+    let _ = std::fs::remove_file(&checkpoint);
+    if let Err(err) = cache.save() {
+        log::error!("Error saving fitness cache: {err}");
+    }
+
+    let mut plots = vec![];
+    if let Some(best) = &best_individual {
+        log::info!(
             "Best individual found: PID = (kp: {:.10}, ki: {:.10}, kd: {:.10}) with fitness {:.10}",
             best.kp(),
             best.ki(),
             best.kd(),
             best.fitness()
         );
-        best.show();
-
-        let cmd = Command::new("python")
-            .arg("plot.py")
-            .arg(dir)
-            .output()
-            .unwrap();
-        if cmd.status.success() {
-            println!("Plot generated successfully.");
-        } else {
-            eprintln!(
-                "Error generating plot: {}",
-                String::from_utf8_lossy(&cmd.stderr)
-            );
+        best.show(&experiment.trace);
+
+        match plot::plot_dir(&run.path(), experiment.language) {
+            Ok(paths) => plots.extend(paths),
+            Err(err) => log::error!("Error generating plots: {err}"),
         }
     } else {
-        println!("No best individual found.");
+        log::warn!("No best individual found.");
+    }
+
+    let genomes = optimizer.genomes();
+    match plot::plot_progress(&run.path(), &history, &genomes, experiment.language) {
+        Ok(paths) => plots.extend(paths),
+        Err(err) => log::error!("Error generating progress plots: {err}"),
+    }
+    log::info!("Generated {} plots.", plots.len());
+
+    let report = Report {
+        run: run.name(),
+        experiment,
+        best: best_individual.as_ref(),
+        baselines: &baselines,
+        history: &history,
+        plots: &plots,
+    };
+    if let Err(err) = report.write(Path::new(&run.file("report.html"))) {
+        log::error!("Error writing report: {err}");
+    }
+
+    if let Err(err) = run.write_manifest() {
+        log::error!("Error writing manifest: {err}");
+    }
+    drop(run_log);
+
+    Ok(())
+}
+
+fn run_seeds(experiment: &Experiment, seeds: &SeedsConfig, overwrite: bool) -> Result<(), String> {
+    let run = RunDir::prepare(&experiment.output_dir, overwrite, false)
+        .map_err(|err| format!("cannot prepare run directory: {err}"))?;
+
+    let log = run.file("log.txt");
+    let run_log = logger::log_to_file(Path::new(&log), false)
+        .map_err(|err| format!("cannot open log {log}: {err}"))?;
+    log::info!("Writing run to {}", run.path().display());
+    let saved = run.file("experiment.toml");
+    experiment
+        .save(&saved)
+        .map_err(|err| format!("cannot save {saved}: {err}"))?;
+    log::info!(
+        "Running the {} over {} seeds, {} at a time...",
+        experiment.optimizer.name(),
+        seeds.runs,
+        seeds.parallel
+    );
+
+    let results = multi_seed::run_seeds(
+        experiment,
+        &multi_seed::seeds(experiment.seed, seeds.runs),
+        seeds.parallel,
+        run.name(),
+        &|| vec![Box::new(InterruptObserver) as Box<dyn Observer>],
+    );
+    if INTERRUPTED.load(Ordering::SeqCst) {
+        log::warn!("Interrupted, discarding the unfinished seeds.");
+        drop(run_log);
+        process::exit(130);
+    }
+
+    let finished = results
+        .iter()
+        .filter_map(|result| result.as_ref().ok().cloned())
+        .collect::<Vec<_>>();
+    if let Err(err) =
+        multi_seed::write_csv(Path::new(&run.file("seeds.csv")), &finished, seeds.target)
+    {
+        log::error!("Error writing seed results: {err}");
+    }
+    match Summary::of(&results, seeds.target) {
+        Some(summary) => {
+            for line in summary.table().lines() {
+                log::info!("{line}");
+            }
+            let written = serde_json::to_vec_pretty(&summary)
+                .map_err(std::io::Error::from)
+                .and_then(|json| std::fs::write(run.file("summary.json"), json));
+            if let Err(err) = written {
+                log::error!("Error writing summary: {err}");
+            }
+        }
+        None => log::warn!("No seed finished."),
+    }
+
+    if let Err(err) = run.write_manifest() {
+        log::error!("Error writing manifest: {err}");
+    }
+    drop(run_log);
+
+    Ok(())
+}
+
+// Warns that a resumed GA keeps its checkpoint settings over a changed experiment.
+fn warn_if_changed(experiment: &Experiment, saved: &str) {
+    match Experiment::load(saved) {
+        Ok(started) => {
+            if serde_json::to_value(&started).ok() != serde_json::to_value(experiment).ok() {
+                log::warn!(
+                    "The experiment changed since {saved} was written; the resumed GA \
+                     keeps its checkpointed settings. Delete the checkpoint to apply them."
+                );
+            }
+        }
+        Err(err) => log::warn!("Cannot compare with the started experiment: {err}"),
+    }
+}
+
+// Runs CMA-ES from `start`, returning the refined best if it beats `start`.
+fn refine(
+    experiment: &Experiment,
+    dir: Arc<str>,
+    cache: FitnessCache,
+    start: &Individual,
+    history: &mut Vec<GenerationStats>,
+    metrics: &str,
+) -> Option<Individual> {
+    let generation = history.last().map_or(0, |stats| stats.generation);
+    let generations = experiment.cma_es.refine_generations;
+    log::info!("Refining the best individual with CMA-ES for {generations} generations...");
+
+    let built = experiment
+        .refine_builder(start.genome(), generation)
+        .with_output_dir(dir)
+        .with_fitness_cache(cache)
+        .build();
+    let mut cma_es = match built {
+        Ok(cma_es) => cma_es,
+        Err(err) => {
+            log::error!("Cannot refine: {err}");
+            return None;
+        }
+    };
+
+    match OpenOptions::new().append(true).open(metrics) {
+        Ok(file) => cma_es.add_observer(Box::new(MetricsObserver::new(file, &[]))),
+        Err(err) => log::error!("Error opening metrics: {err}"),
+    }
+    cma_es.add_observer(Box::new(LogObserver));
+    cma_es.add_observer(Box::new(InterruptObserver));
+
+    // Failures are reported by the log observer and keep the unrefined best.
+    let _ = cma_es.run(generation + generations);
+    log::info!("Refinement ended with step size {:.3e}", cma_es.step_size());
+
+    // The first entry is the starting point, already the last generation.
+    history.extend(cma_es.statistics().iter().skip(1).cloned());
+    cma_es
+        .best()
+        .filter(|best| best.fitness() < start.fitness())
+}
+
+// Stops the run after the current generation once Ctrl-C was pressed.
+struct InterruptObserver;
+
+impl Observer for InterruptObserver {
+    fn on_generation_end(
+        &mut self,
+        _stats: &GenerationStats,
+        _best: &Individual,
+    ) -> ControlFlow<()> {
+        if INTERRUPTED.load(Ordering::SeqCst) {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
     }
 }
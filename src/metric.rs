@@ -0,0 +1,70 @@
+use aule::prelude::{Block, Continuous, Signal};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    #[default]
+    Iae,
+    Ise,
+    Itae,
+    Itse,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 4] = [Metric::Iae, Metric::Ise, Metric::Itae, Metric::Itse];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::Iae => "IAE",
+            Metric::Ise => "ISE",
+            Metric::Itae => "ITAE",
+            Metric::Itse => "ITSE",
+        }
+    }
+}
+
+pub struct ErrorMetric {
+    metric: Metric,
+    values: [f32; 4],
+}
+
+impl ErrorMetric {
+    pub fn new(metric: Metric) -> Self {
+        Self {
+            metric,
+            values: [0.0; 4],
+        }
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value_of(self.metric)
+    }
+
+    pub fn value_of(&self, metric: Metric) -> f32 {
+        self.values[metric as usize]
+    }
+}
+
+impl Block for ErrorMetric {
+    type Input = f32;
+    type Output = f32;
+    type TimeType = Continuous;
+
+    fn output(
+        &mut self,
+        input: Signal<Self::Input, Self::TimeType>,
+    ) -> Signal<Self::Output, Self::TimeType> {
+        let dt = input.delta.dt().as_secs_f32();
+        let t = input.delta.sim_time().as_secs_f32();
+        let error = input.value;
+
+        self.values[Metric::Iae as usize] += error.abs() * dt;
+        self.values[Metric::Ise as usize] += error * error * dt;
+        self.values[Metric::Itae as usize] += t * error.abs() * dt;
+        self.values[Metric::Itse as usize] += t * error * error * dt;
+
+        let value = self.value();
+        input.map(|_| value)
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    fmt::Write as _,
+    fs, io,
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::Instant,
+};
+
+use serde::Serialize;
+
+use crate::{
+    cache::FitnessCache,
+    experiment::{Algorithm, Experiment},
+    observer::Observer,
+    optimizer::{Optimizer, OptimizerError},
+    work::stream_seed,
+};
+
+/// Observers added to every optimiser of a multi-seed run.
+pub type ObserverFactory = dyn Fn() -> Vec<Box<dyn Observer>> + Sync;
+
+/// Seeds of `runs` runs: `seed` itself, then seeds derived from it.
+pub fn seeds(seed: u64, runs: usize) -> Vec<u64> {
+    (0..runs)
+        .map(|i| if i == 0 { seed } else { stream_seed(seed, i) })
+        .collect()
+}
+
+/// Final result of the run with one seed.
+#[derive(Clone, Debug, Serialize)]
+pub struct SeedResult {
+    pub seed: u64,
+    pub fitness: f32,
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    pub generations: usize,
+    pub evaluations: usize,
+    pub elapsed_secs: f64,
+}
+
+/// Runs `experiment` with only its optimiser reseeded with `seed`.
+pub fn run_seed(
+    experiment: &Experiment,
+    seed: u64,
+    dir: &str,
+    observers: &ObserverFactory,
+) -> Result<SeedResult, OptimizerError> {
+    let start = Instant::now();
+    let experiment = Experiment {
+        seed,
+        objective_seed: Some(experiment.objective_seed.unwrap_or(experiment.seed)),
+        ..experiment.clone()
+    };
+    let cache = FitnessCache::default();
+    let dir: Arc<str> = dir.into();
+
+    let mut optimizer = experiment.optimizer(dir.clone(), cache.clone())?;
+    for observer in observers() {
+        optimizer.add_observer(observer);
+    }
+    optimizer.run(experiment.ga.generations)?;
+    let mut best = optimizer.best().ok_or(OptimizerError::EmptyPopulation)?;
+    let mut history = optimizer.statistics().to_vec();
+
+    if experiment.cma_es.refine_generations > 0 && experiment.optimizer != Algorithm::CmaEs {
+        let generation = history.last().map_or(0, |stats| stats.generation);
+        let mut cma_es = experiment
+            .refine_builder(best.genome(), generation)
+            .with_output_dir(dir)
+            .with_fitness_cache(cache)
+            .build()?;
+        for observer in observers() {
+            cma_es.add_observer(observer);
+        }
+        cma_es.run(generation + experiment.cma_es.refine_generations)?;
+        // The first entry is the starting point, already the last generation.
+        history.extend(cma_es.statistics().iter().skip(1).cloned());
+        if let Some(refined) = cma_es.best().filter(|ind| ind.fitness() < best.fitness()) {
+            best = refined;
+        }
+    }
+
+    Ok(SeedResult {
+        seed,
+        fitness: best.fitness(),
+        kp: best.kp(),
+        ki: best.ki(),
+        kd: best.kd(),
+        generations: history.last().map_or(0, |stats| stats.generation),
+        evaluations: history.iter().map(|stats| stats.evaluations).sum(),
+        elapsed_secs: start.elapsed().as_secs_f64(),
+    })
+}
+
+/// Runs `experiment` once per seed, `parallel` seeds sharing the workers.
+pub fn run_seeds(
+    experiment: &Experiment,
+    seeds: &[u64],
+    parallel: usize,
+    dir: &str,
+    observers: &ObserverFactory,
+) -> Vec<Result<SeedResult, OptimizerError>> {
+    let threads = parallel.clamp(1, seeds.len().max(1));
+    let mut experiment = experiment.clone();
+    if experiment.ga.parallel_works > 0 {
+        experiment.ga.parallel_works = (experiment.ga.parallel_works / threads).max(1);
+    }
+    let experiment = &experiment;
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(seeds.iter().map(|_| None).collect::<Vec<_>>());
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&seed) = seeds.get(i) else {
+                        break;
+                    };
+                    let result = run_seed(experiment, seed, dir, observers);
+                    match &result {
+                        Ok(result) => log::info!(
+                            "Seed {seed:#x}: PID = (kp: {:.6}, ki: {:.6}, kd: {:.6}) with fitness {:.10}",
+                            result.kp,
+                            result.ki,
+                            result.kd,
+                            result.fitness
+                        ),
+                        Err(err) => log::error!("Seed {seed:#x} failed: {err}"),
+                    }
+                    results.lock().unwrap()[i] = Some(result);
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every seed is run"))
+        .collect()
+}
+
+/// Mean, sample standard deviation, minimum and maximum of a value over runs.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Spread {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Spread {
+    pub fn of(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = if values.len() > 1 {
+            values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (n - 1.0)
+        } else {
+            0.0
+        };
+
+        Some(Self {
+            mean,
+            std_dev: variance.sqrt(),
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        })
+    }
+}
+
+/// Aggregate of a multi-seed run over the runs that finished.
+#[derive(Clone, Debug, Serialize)]
+pub struct Summary {
+    pub runs: usize,
+    pub failed: usize,
+    pub fitness: Spread,
+    pub kp: Spread,
+    pub ki: Spread,
+    pub kd: Spread,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<f32>,
+    /// Runs whose final fitness is at most `target`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub successes: Option<usize>,
+}
+
+impl Summary {
+    /// `None` when no run finished.
+    pub fn of(results: &[Result<SeedResult, OptimizerError>], target: Option<f32>) -> Option<Self> {
+        let finished: Vec<&SeedResult> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+        let spread = |value: fn(&SeedResult) -> f32| {
+            Spread::of(
+                &finished
+                    .iter()
+                    .map(|result| value(result) as f64)
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        Some(Self {
+            runs: results.len(),
+            failed: results.len() - finished.len(),
+            fitness: spread(|result| result.fitness)?,
+            kp: spread(|result| result.kp)?,
+            ki: spread(|result| result.ki)?,
+            kd: spread(|result| result.kd)?,
+            target,
+            successes: target.map(|target| {
+                finished
+                    .iter()
+                    .filter(|result| result.fitness <= target)
+                    .count()
+            }),
+        })
+    }
+
+    /// Fraction of all runs, failed ones included, that reached the target.
+    pub fn success_rate(&self) -> Option<f64> {
+        self.successes
+            .map(|successes| successes as f64 / self.runs as f64)
+    }
+
+    /// Plain-text table with one row per value, for logs.
+    pub fn table(&self) -> String {
+        let mut table = format!(
+            "Summary over {} runs ({} failed)\n{:<8}{:>16}{:>16}{:>16}{:>16}\n",
+            self.runs, self.failed, "", "mean", "std", "min", "max"
+        );
+        for (name, spread) in [
+            ("fitness", self.fitness),
+            ("kp", self.kp),
+            ("ki", self.ki),
+            ("kd", self.kd),
+        ] {
+            let _ = writeln!(
+                table,
+                "{name:<8}{:>16.10}{:>16.10}{:>16.10}{:>16.10}",
+                spread.mean, spread.std_dev, spread.min, spread.max
+            );
+        }
+        if let (Some(target), Some(successes), Some(rate)) =
+            (self.target, self.successes, self.success_rate())
+        {
+            let _ = writeln!(
+                table,
+                "Success rate: {successes}/{} ({:.1}%) with fitness <= {target}",
+                self.runs,
+                100.0 * rate
+            );
+        }
+
+        table
+    }
+}
+
+/// Writes one CSV row per finished run, with whether it reached `target`.
+pub fn write_csv(path: &Path, results: &[SeedResult], target: Option<f32>) -> io::Result<()> {
+    let mut csv =
+        String::from("seed,fitness,kp,ki,kd,generations,evaluations,elapsed_secs,success\n");
+    for result in results {
+        let success = target.map_or(String::new(), |target| {
+            (result.fitness <= target).to_string()
+        });
+        let _ = writeln!(
+            csv,
+            "{:#x},{},{},{},{},{},{},{:.3},{success}",
+            result.seed,
+            result.fitness,
+            result.kp,
+            result.ki,
+            result.kd,
+            result.generations,
+            result.evaluations,
+            result.elapsed_secs
+        );
+    }
+
+    fs::write(path, csv)
+}
@@ -0,0 +1,162 @@
+use std::{io::Write, ops::ControlFlow};
+
+use crate::{individual::Individual, optimizer::OptimizerError, stats::GenerationStats};
+
+/// Why [`Optimizer::run`](crate::Optimizer::run) returned.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Termination {
+    /// The requested number of generations was reached.
+    Completed,
+    /// An observer asked to stop.
+    Stopped,
+    /// A generation could not be evaluated.
+    Failed(OptimizerError),
+}
+
+/// Hooks called by an optimiser as it evolves; every method defaults to nothing.
+pub trait Observer {
+    /// Called before breeding `generation`.
+    fn on_generation_start(&mut self, _generation: usize) {}
+
+    /// Called once `stats.generation` is evaluated; `Break` stops the run.
+    fn on_generation_end(
+        &mut self,
+        _stats: &GenerationStats,
+        _best: &Individual,
+    ) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called before `on_generation_end` when the best fitness improved.
+    fn on_new_best(&mut self, _stats: &GenerationStats, _best: &Individual) {}
+
+    /// Called once when the run ends, with the statistics of every generation.
+    fn on_termination(&mut self, _termination: &Termination, _history: &[GenerationStats]) {}
+}
+
+/// Observers of one optimiser, with the stop requests they made.
+#[derive(Default)]
+pub struct Observers {
+    observers: Vec<Box<dyn Observer>>,
+    stop_requested: bool,
+}
+
+impl Observers {
+    pub fn push(&mut self, observer: Box<dyn Observer>) {
+        self.observers.push(observer);
+    }
+
+    pub fn generation_start(&mut self, generation: usize) {
+        for observer in &mut self.observers {
+            observer.on_generation_start(generation);
+        }
+    }
+
+    /// Reports an evaluated generation, calling `on_new_best` first if `improved`.
+    pub fn generation_end(&mut self, stats: &GenerationStats, best: &Individual, improved: bool) {
+        for observer in &mut self.observers {
+            if improved {
+                observer.on_new_best(stats, best);
+            }
+            if observer.on_generation_end(stats, best).is_break() {
+                self.stop_requested = true;
+            }
+        }
+    }
+
+    pub fn terminate(&mut self, termination: &Termination, history: &[GenerationStats]) {
+        for observer in &mut self.observers {
+            observer.on_termination(termination, history);
+        }
+    }
+
+    /// Whether an observer asked to stop since the last call.
+    pub fn take_stop(&mut self) -> bool {
+        std::mem::take(&mut self.stop_requested)
+    }
+}
+
+/// Reports the progress of a run through the `log` crate.
+pub struct LogObserver;
+
+impl Observer for LogObserver {
+    fn on_generation_start(&mut self, generation: usize) {
+        log::debug!("Evolving generation {generation}");
+    }
+
+    fn on_generation_end(
+        &mut self,
+        stats: &GenerationStats,
+        _best: &Individual,
+    ) -> ControlFlow<()> {
+        log::info!(
+            "Generation {}: {} individuals, best {:.10}, mean {:.6}, diversity {:.6}, {} screened, {} filtered, cache {}/{} hits ({:.2}s)",
+            stats.generation,
+            stats.size,
+            stats.best,
+            stats.mean,
+            stats.diversity,
+            stats.screened,
+            stats.filtered,
+            stats.cache_hits,
+            stats.evaluations,
+            stats.elapsed_secs
+        );
+        ControlFlow::Continue(())
+    }
+
+    fn on_new_best(&mut self, _stats: &GenerationStats, best: &Individual) {
+        log::debug!(
+            "New best PID = (kp: {:.10}, ki: {:.10}, kd: {:.10}) with fitness {:.10}",
+            best.kp(),
+            best.ki(),
+            best.kd(),
+            best.fitness()
+        );
+    }
+
+    fn on_termination(&mut self, termination: &Termination, history: &[GenerationStats]) {
+        let generation = history.last().map_or(0, |stats| stats.generation);
+        match termination {
+            Termination::Completed => log::info!("Finished after {generation} generations"),
+            Termination::Stopped => log::warn!("Stopped after {generation} generations"),
+            Termination::Failed(err) => {
+                log::error!("Generation {} failed: {err}", generation + 1)
+            }
+        }
+    }
+}
+
+/// Streams one JSON object per generation, e.g. to a `metrics.jsonl` file.
+pub struct MetricsObserver<W: Write> {
+    out: W,
+}
+
+impl<W: Write> MetricsObserver<W> {
+    /// Starts the stream with the generations already in `history`.
+    pub fn new(out: W, history: &[GenerationStats]) -> Self {
+        let mut observer = Self { out };
+        for stats in history {
+            observer.write(stats);
+        }
+        observer
+    }
+
+    fn write(&mut self, stats: &GenerationStats) {
+        let line = serde_json::to_string(stats).unwrap_or_default();
+        if let Err(err) = writeln!(self.out, "{line}").and_then(|_| self.out.flush()) {
+            log::error!("Error writing metrics: {err}");
+        }
+    }
+}
+
+impl<W: Write> Observer for MetricsObserver<W> {
+    fn on_generation_end(
+        &mut self,
+        stats: &GenerationStats,
+        _best: &Individual,
+    ) -> ControlFlow<()> {
+        self.write(stats);
+        ControlFlow::Continue(())
+    }
+}
@@ -0,0 +1,138 @@
+use std::{fmt, io};
+
+use crate::{
+    individual::{Genome, Individual},
+    observer::{Observer, Observers, Termination},
+    stats::GenerationStats,
+};
+
+/// Why an optimiser could not be built or advanced.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptimizerError {
+    /// A required builder parameter was never set.
+    Missing(&'static str),
+    /// A parameter is out of its valid range.
+    Invalid(&'static str, String),
+    /// The gain bounds cannot produce a usable initial population.
+    Bounds(String),
+    /// The objective has invalid simulation settings or scenarios.
+    Objective(String),
+    /// No individual with a finite fitness is left.
+    EmptyPopulation,
+    /// An individual with a NaN or infinite fitness reached selection.
+    NonFiniteFitness(f32),
+}
+
+impl fmt::Display for OptimizerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptimizerError::Missing(name) => write!(f, "{name} is required"),
+            OptimizerError::Invalid(name, msg) => write!(f, "{name} {msg}"),
+            OptimizerError::Bounds(msg) => write!(f, "invalid bounds: {msg}"),
+            OptimizerError::Objective(msg) => write!(f, "invalid objective: {msg}"),
+            OptimizerError::EmptyPopulation => {
+                write!(f, "population has no individual with a finite fitness")
+            }
+            OptimizerError::NonFiniteFitness(fitness) => {
+                write!(f, "non-finite fitness {fitness} in tournament selection")
+            }
+        }
+    }
+}
+
+impl std::error::Error for OptimizerError {}
+
+pub(crate) fn check_rate(name: &'static str, rate: f32) -> Result<(), OptimizerError> {
+    if (0.0..=1.0).contains(&rate) {
+        Ok(())
+    } else {
+        Err(OptimizerError::Invalid(
+            name,
+            format!("must be between 0 and 1, got {rate}"),
+        ))
+    }
+}
+
+// Checks the `[max_kp, max_ki, max_kd]` bounds shared by every backend.
+pub(crate) fn check_bounds(bounds: [f32; 3]) -> Result<(), OptimizerError> {
+    for (name, bound) in ["max_kp", "max_ki", "max_kd"].iter().zip(bounds) {
+        if !(bound.is_finite() && bound >= 0.0) {
+            return Err(OptimizerError::Bounds(format!(
+                "{name} must be a non-negative number, got {bound}"
+            )));
+        }
+    }
+    if bounds.iter().all(|bound| *bound == 0.0) {
+        return Err(OptimizerError::Bounds(
+            "max_kp, max_ki and max_kd are all zero".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Search strategy over PID gains, scored with the same [`Individual`] fitness.
+pub trait Optimizer {
+    /// Draws and evaluates a new starting population at generation 0.
+    fn initialise(&mut self) -> Result<(), OptimizerError>;
+
+    /// Advances one generation and returns the best individual so far.
+    fn step(&mut self) -> Result<Individual, OptimizerError>;
+
+    /// Best individual found so far.
+    fn best(&self) -> Option<Individual>;
+
+    /// Statistics of every generation so far, starting with generation 0.
+    fn statistics(&self) -> &[GenerationStats];
+
+    /// Number of generations evaluated since the starting population.
+    fn generation(&self) -> usize;
+
+    /// Genomes currently searched, best first, e.g. for population plots.
+    fn genomes(&self) -> Vec<Genome>;
+
+    /// Observers notified by [`step`](Self::step) and [`run`](Self::run).
+    fn observers(&mut self) -> &mut Observers;
+
+    /// Called by [`run`](Self::run) once the last generation is reached.
+    fn finish(&mut self) -> Result<(), OptimizerError> {
+        Ok(())
+    }
+
+    /// Saves the state needed to resume the run, for backends that support it.
+    fn save_checkpoint(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Adds an observer notified of every generation; see [`Observer`].
+    fn add_observer(&mut self, observer: Box<dyn Observer>) {
+        self.observers().push(observer);
+    }
+
+    /// Steps until `generations`, a stop request or a failure.
+    fn run(&mut self, generations: usize) -> Result<Termination, OptimizerError> {
+        self.observers().take_stop();
+        let termination = loop {
+            if self.generation() >= generations {
+                break match self.finish() {
+                    Ok(()) => Termination::Completed,
+                    Err(err) => Termination::Failed(err),
+                };
+            }
+            if let Err(err) = self.step() {
+                break Termination::Failed(err);
+            }
+            if self.observers().take_stop() {
+                break Termination::Stopped;
+            }
+        };
+
+        let history = self.statistics().to_vec();
+        self.observers().terminate(&termination, &history);
+
+        match termination {
+            Termination::Failed(err) => Err(err),
+            termination => Ok(termination),
+        }
+    }
+}
@@ -0,0 +1,432 @@
+use std::{
+    fs, io,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use pid_opt::{experiment::Language, individual::Genome, stats::GenerationStats};
+use plotters::prelude::*;
+
+const SIZE: (u32, u32) = (1000, 900);
+const FONT: &str = "sans-serif";
+
+struct Labels {
+    time: &'static str,
+    amplitude: &'static str,
+    reference: &'static str,
+    output: &'static str,
+    control: &'static str,
+    error: &'static str,
+    generation: &'static str,
+    fitness: &'static str,
+    best: &'static str,
+    mean: &'static str,
+    worst: &'static str,
+    convergence: &'static str,
+    genes: &'static str,
+    population: &'static str,
+}
+
+fn labels(language: Language) -> Labels {
+    match language {
+        Language::English => Labels {
+            time: "Time (s)",
+            amplitude: "Amplitude",
+            reference: "Reference",
+            output: "Output",
+            control: "Control signal",
+            error: "Error",
+            generation: "Generation",
+            fitness: "Fitness",
+            best: "Best",
+            mean: "Mean",
+            worst: "Worst",
+            convergence: "Convergence",
+            genes: "Gains of the best individual",
+            population: "Final population",
+        },
+        Language::Portuguese => Labels {
+            time: "Tempo (s)",
+            amplitude: "Amplitude",
+            reference: "Referência",
+            output: "Saída",
+            control: "Sinal de controle",
+            error: "Erro",
+            generation: "Geração",
+            fitness: "Aptidão",
+            best: "Melhor",
+            mean: "Média",
+            worst: "Pior",
+            convergence: "Convergência",
+            genes: "Ganhos do melhor indivíduo",
+            population: "População final",
+        },
+    }
+}
+
+/// Columns of a trace CSV written by the simulation, looked up by header name.
+pub struct Trace {
+    columns: Vec<(String, Vec<f32>)>,
+}
+
+impl Trace {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut lines = text.lines().filter(|line| !line.starts_with('#'));
+        let header = lines
+            .next()
+            .ok_or_else(|| io::Error::other(format!("{}: empty trace", path.display())))?;
+        let mut columns = header
+            .split(',')
+            .map(|name| (name.trim().to_string(), vec![]))
+            .collect::<Vec<_>>();
+
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            for ((_, values), value) in columns.iter_mut().zip(line.split(',')) {
+                let value = value.trim().parse::<f32>().map_err(|err| {
+                    io::Error::other(format!("{}: {err} in `{line}`", path.display()))
+                })?;
+                values.push(value);
+            }
+        }
+
+        Ok(Self { columns })
+    }
+
+    pub fn column(&self, name: &str) -> Option<&[f32]> {
+        self.columns
+            .iter()
+            .find(|(column, _)| column == name)
+            .map(|(_, values)| values.as_slice())
+    }
+}
+
+fn range(series: &[&[f32]]) -> Range<f32> {
+    let (min, max) = series
+        .iter()
+        .flat_map(|values| values.iter())
+        .filter(|value| value.is_finite())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &value| {
+            (min.min(value), max.max(value))
+        });
+
+    if min > max {
+        return -1.0..1.0;
+    }
+    let margin = ((max - min) * 0.05).max(1e-3);
+    (min - margin)..(max + margin)
+}
+
+fn draw_panel<DB: DrawingBackend>(
+    area: &DrawingArea<DB, plotters::coord::Shift>,
+    t: &[f32],
+    series: &[(&str, &[f32], RGBColor)],
+    x_desc: &str,
+    y_desc: &str,
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let x_range = range(&[t]);
+    let y_range = range(
+        &series
+            .iter()
+            .map(|(_, values, _)| *values)
+            .collect::<Vec<_>>(),
+    );
+
+    let mut chart = ChartBuilder::on(area)
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(70)
+        .build_cartesian_2d(x_range, y_range)?;
+    chart
+        .configure_mesh()
+        .x_desc(x_desc)
+        .y_desc(y_desc)
+        .light_line_style(BLACK.mix(0.05))
+        .draw()?;
+
+    for &(label, values, color) in series {
+        let points = t.iter().copied().zip(values.iter().copied());
+        chart
+            .draw_series(LineSeries::new(points, color.stroke_width(2)))?
+            .label(label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .position(SeriesLabelPosition::UpperRight)
+        .draw()?;
+
+    Ok(())
+}
+
+/// Output, control signal and error of one scenario in a single SVG.
+pub fn plot_trace(trace: &Trace, title: &str, path: &Path, language: Language) -> io::Result<()> {
+    let labels = labels(language);
+    let column = |name: &str| {
+        trace
+            .column(name)
+            .ok_or_else(|| io::Error::other(format!("trace has no `{name}` column")))
+    };
+    let t = column("t")?;
+    let input = column("input")?;
+    let output = column("output")?;
+    let control = column("control")?;
+    let error = column("error")?;
+
+    let draw = || -> Result<(), DrawingAreaErrorKind<_>> {
+        let root = SVGBackend::new(path, SIZE).into_drawing_area();
+        root.fill(&WHITE)?;
+        let root = root.titled(title, (FONT, 24))?;
+        let panels = root.split_evenly((3, 1));
+
+        draw_panel(
+            &panels[0],
+            t,
+            &[
+                (labels.reference, input, BLUE),
+                (labels.output, output, RED),
+            ],
+            labels.time,
+            labels.amplitude,
+        )?;
+        draw_panel(
+            &panels[1],
+            t,
+            &[(labels.control, control, GREEN)],
+            labels.time,
+            labels.control,
+        )?;
+        draw_panel(
+            &panels[2],
+            t,
+            &[(labels.error, error, MAGENTA)],
+            labels.time,
+            labels.error,
+        )?;
+
+        root.present()
+    };
+
+    draw().map_err(|err| io::Error::other(format!("{}: {err}", path.display())))
+}
+
+/// Plots every trace CSV in `dir` to `plot_<scenario>.svg` next to it.
+pub fn plot_dir(dir: &Path, language: Language) -> io::Result<Vec<PathBuf>> {
+    let mut traces = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "csv"))
+        .collect::<Vec<_>>();
+    traces.sort();
+
+    let mut plots = vec![];
+    for csv in traces {
+        let name = csv
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let svg = dir.join(format!("plot_{name}.svg"));
+        plot_trace(&Trace::load(&csv)?, &name, &svg, language)?;
+        plots.push(svg);
+    }
+
+    Ok(plots)
+}
+
+/// Best, mean and worst fitness per generation on a log scale.
+pub fn plot_convergence(
+    history: &[GenerationStats],
+    path: &Path,
+    language: Language,
+) -> io::Result<()> {
+    let labels = labels(language);
+    let generations = history
+        .iter()
+        .map(|stats| stats.generation as f32)
+        .collect::<Vec<_>>();
+    let positive = |value: f32| value.max(f32::MIN_POSITIVE);
+    let series = [
+        (
+            labels.best,
+            history.iter().map(|s| positive(s.best)).collect::<Vec<_>>(),
+            BLUE,
+        ),
+        (
+            labels.mean,
+            history.iter().map(|s| positive(s.mean)).collect(),
+            GREEN,
+        ),
+        (
+            labels.worst,
+            history.iter().map(|s| positive(s.worst)).collect(),
+            RED,
+        ),
+    ];
+
+    let draw = || -> Result<(), DrawingAreaErrorKind<_>> {
+        let root = SVGBackend::new(path, (SIZE.0, SIZE.1 / 2)).into_drawing_area();
+        root.fill(&WHITE)?;
+        let root = root.titled(labels.convergence, (FONT, 24))?;
+
+        let y_range = range(
+            &series
+                .iter()
+                .map(|(_, values, _)| values.as_slice())
+                .collect::<Vec<_>>(),
+        );
+        let y_range = positive(y_range.start)..positive(y_range.end);
+        let mut chart = ChartBuilder::on(&root)
+            .margin(10)
+            .x_label_area_size(40)
+            .y_label_area_size(80)
+            .build_cartesian_2d(range(&[&generations]), y_range.log_scale())?;
+        chart
+            .configure_mesh()
+            .x_desc(labels.generation)
+            .y_desc(labels.fitness)
+            .y_label_formatter(&|value| format!("{value:.1e}"))
+            .light_line_style(BLACK.mix(0.05))
+            .draw()?;
+
+        for (label, values, color) in &series {
+            let color = *color;
+            let points = generations.iter().copied().zip(values.iter().copied());
+            chart
+                .draw_series(LineSeries::new(points, color.stroke_width(2)))?
+                .label(*label)
+                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+        }
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .position(SeriesLabelPosition::UpperRight)
+            .draw()?;
+
+        root.present()
+    };
+
+    draw().map_err(|err| io::Error::other(format!("{}: {err}", path.display())))
+}
+
+/// kp, ki and kd of the best individual per generation, one panel each.
+pub fn plot_genes(history: &[GenerationStats], path: &Path, language: Language) -> io::Result<()> {
+    let labels = labels(language);
+    let generations = history
+        .iter()
+        .map(|stats| stats.generation as f32)
+        .collect::<Vec<_>>();
+    let kp = history
+        .iter()
+        .map(|stats| stats.best_kp)
+        .collect::<Vec<_>>();
+    let ki = history
+        .iter()
+        .map(|stats| stats.best_ki)
+        .collect::<Vec<_>>();
+    let kd = history
+        .iter()
+        .map(|stats| stats.best_kd)
+        .collect::<Vec<_>>();
+
+    let draw = || -> Result<(), DrawingAreaErrorKind<_>> {
+        let root = SVGBackend::new(path, SIZE).into_drawing_area();
+        root.fill(&WHITE)?;
+        let root = root.titled(labels.genes, (FONT, 24))?;
+        let panels = root.split_evenly((3, 1));
+
+        for (panel, (name, values, color)) in
+            panels
+                .iter()
+                .zip([("kp", &kp, BLUE), ("ki", &ki, GREEN), ("kd", &kd, RED)])
+        {
+            draw_panel(
+                panel,
+                &generations,
+                &[(name, values, color)],
+                labels.generation,
+                name,
+            )?;
+        }
+
+        root.present()
+    };
+
+    draw().map_err(|err| io::Error::other(format!("{}: {err}", path.display())))
+}
+
+/// Pairwise scatter of the final population in gain space.
+pub fn plot_population(genomes: &[Genome], path: &Path, language: Language) -> io::Result<()> {
+    let labels = labels(language);
+    let gene = |genome: &Genome, index: usize| [genome.kp, genome.ki, genome.kd][index];
+    let names = ["kp", "ki", "kd"];
+
+    let draw = || -> Result<(), DrawingAreaErrorKind<_>> {
+        let root = SVGBackend::new(path, (SIZE.0 * 3 / 2, SIZE.1 / 2)).into_drawing_area();
+        root.fill(&WHITE)?;
+        let root = root.titled(labels.population, (FONT, 24))?;
+        let panels = root.split_evenly((1, 3));
+
+        for (panel, (x, y)) in panels.iter().zip([(0, 1), (0, 2), (1, 2)]) {
+            let xs = genomes.iter().map(|g| gene(g, x)).collect::<Vec<_>>();
+            let ys = genomes.iter().map(|g| gene(g, y)).collect::<Vec<_>>();
+
+            let mut chart = ChartBuilder::on(panel)
+                .margin(10)
+                .x_label_area_size(40)
+                .y_label_area_size(70)
+                .build_cartesian_2d(range(&[&xs]), range(&[&ys]))?;
+            chart
+                .configure_mesh()
+                .x_desc(names[x])
+                .y_desc(names[y])
+                .light_line_style(BLACK.mix(0.05))
+                .draw()?;
+
+            chart.draw_series(
+                xs.iter()
+                    .zip(&ys)
+                    .map(|(&x, &y)| Circle::new((x, y), 2, BLUE.mix(0.5).filled())),
+            )?;
+            // Populations are kept sorted, so the first genome is the best.
+            if let (Some(&x), Some(&y)) = (xs.first(), ys.first()) {
+                chart
+                    .draw_series([Circle::new((x, y), 5, RED.filled())])?
+                    .label(labels.best)
+                    .legend(|(x, y)| Circle::new((x, y), 5, RED.filled()));
+                chart
+                    .configure_series_labels()
+                    .background_style(WHITE.mix(0.8))
+                    .border_style(BLACK)
+                    .draw()?;
+            }
+        }
+
+        root.present()
+    };
+
+    draw().map_err(|err| io::Error::other(format!("{}: {err}", path.display())))
+}
+
+/// Convergence, gene trajectory and final population plots of a GA run.
+pub fn plot_progress(
+    dir: &Path,
+    history: &[GenerationStats],
+    genomes: &[Genome],
+    language: Language,
+) -> io::Result<Vec<PathBuf>> {
+    let plots = [
+        dir.join("convergence.svg"),
+        dir.join("genes.svg"),
+        dir.join("population.svg"),
+    ];
+    plot_convergence(history, &plots[0], language)?;
+    plot_genes(history, &plots[1], language)?;
+    plot_population(genomes, &plots[2], language)?;
+
+    Ok(plots.to_vec())
+}
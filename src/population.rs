@@ -1,57 +1,176 @@
+use std::sync::Arc;
+
 use rand::{Rng, SeedableRng, rngs::StdRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    individual::{Individual, Model},
-    work::{Work, work_pool},
+    cache::FitnessCache,
+    individual::{Genome, Individual},
+    scenario::Objective,
+    work::{Work, stream_seed, work_pool, work_serial},
 };
 
+// Stream of the seeding perturbations, apart from the per-individual ones.
+const SEEDING_STREAM: usize = usize::MAX;
+/// Largest population kept after sorting; optimisers reject larger sizes.
+pub const MAX_POPULATION_SIZE: usize = 1_000;
+
+/// Known gains to start part of a population from.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Seeding {
+    pub genomes: Vec<Genome>,
+    /// Fraction of the population drawn around the seeds.
+    #[serde(default)]
+    pub ratio: f32,
+    /// Largest move of the other copies, as a fraction of each gain's bound.
+    #[serde(default)]
+    pub radius: f32,
+}
+
+impl Seeding {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.ratio) {
+            return Err(format!("ratio must be between 0 and 1, got {}", self.ratio));
+        }
+        if !(self.radius.is_finite() && self.radius >= 0.0) {
+            return Err(format!(
+                "radius must be a non-negative number, got {}",
+                self.radius
+            ));
+        }
+        for genome in &self.genomes {
+            if ![genome.kp, genome.ki, genome.kd]
+                .iter()
+                .all(|gain| gain.is_finite())
+            {
+                return Err(format!("genome {genome:?} has a non-finite gain"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Seeds, then copies moved by up to `radius` times each bound.
+    pub fn sample(&self, size: usize, bounds: [f32; 3], seed: u64) -> Vec<Genome> {
+        if self.genomes.is_empty() {
+            return vec![];
+        }
+        let count = ((self.ratio * size as f32).round() as usize)
+            .max(self.genomes.len())
+            .min(size);
+
+        let mut rng = StdRng::seed_from_u64(stream_seed(seed, SEEDING_STREAM));
+        let mut offset = |bound: f32| self.radius * bound * (2.0 * rng.random::<f32>() - 1.0);
+        self.genomes
+            .iter()
+            .cycle()
+            .take(count)
+            .enumerate()
+            .map(|(i, genome)| {
+                if i < self.genomes.len() {
+                    genome.clamp(bounds)
+                } else {
+                    let [max_kp, max_ki, max_kd] = bounds;
+                    Genome::new(
+                        genome.kp + offset(max_kp),
+                        genome.ki + offset(max_ki),
+                        genome.kd + offset(max_kd),
+                    )
+                    .clamp(bounds)
+                }
+            })
+            .collect()
+    }
+}
+
 #[derive(Clone)]
 pub struct Population {
     individuals: Vec<Individual>,
-    rng: StdRng,
+    rng: ChaCha12Rng,
+    filtered: usize,
 }
 //0.0031834461
 impl Population {
     pub fn new(
         size: usize,
-        model: Model,
-        dir: &'static str,
+        objective: Arc<Objective>,
+        dir: Arc<str>,
         max_kp: f32,
         max_ki: f32,
         max_kd: f32,
         seed: u64,
+        cache: FitnessCache,
     ) -> Self {
-        let individuals = NewRandomPopulation::new(model, dir, max_kp, max_ki, max_kd, seed)
-            .work((0..size).map(|_| ()).collect());
-        let rng = StdRng::seed_from_u64(seed);
+        let individuals = work_serial(
+            (0..size).map(|_| ()).collect(),
+            NewRandomPopulation::new(objective, dir, max_kp, max_ki, max_kd, seed, cache),
+        );
+        let rng = ChaCha12Rng::seed_from_u64(seed);
 
-        Self { individuals, rng }.sorted()
+        Self {
+            individuals,
+            rng,
+            filtered: 0,
+        }
+        .sorted()
     }
 
     pub fn new_parallel(
         size: usize,
         works: usize,
-        model: Model,
-        dir: &'static str,
+        objective: Arc<Objective>,
+        dir: Arc<str>,
         max_kp: f32,
         max_ki: f32,
         max_kd: f32,
         seed: u64,
+        cache: FitnessCache,
     ) -> Self {
         let individuals = work_pool(
             works,
             (0..size).map(|_| ()).collect(),
-            NewRandomPopulation::new(model, dir, max_kp, max_ki, max_kd, seed),
+            NewRandomPopulation::new(objective, dir, max_kp, max_ki, max_kd, seed, cache),
         );
-        let rng = StdRng::seed_from_u64(seed);
+        let rng = ChaCha12Rng::seed_from_u64(seed);
 
-        Self { individuals, rng }.sorted()
+        Self {
+            individuals,
+            rng,
+            filtered: 0,
+        }
+        .sorted()
+    }
+
+    pub fn from_parts(individuals: Vec<Individual>, rng: ChaCha12Rng) -> Self {
+        Self {
+            individuals,
+            rng,
+            filtered: 0,
+        }
     }
 
     pub fn len(&self) -> usize {
         self.individuals.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.individuals.is_empty()
+    }
+
+    pub fn individuals(&self) -> &[Individual] {
+        &self.individuals
+    }
+
+    pub fn rng(&self) -> &ChaCha12Rng {
+        &self.rng
+    }
+
+    pub fn filtered(&self) -> usize {
+        self.filtered
+    }
+
     fn sorted(mut self) -> Self {
         let size_before_filter = self.individuals.len();
         let inds = self
@@ -60,14 +179,16 @@ impl Population {
             .filter(|ind| ind.fitness().is_finite())
             .collect::<Vec<_>>();
         let size_after_filter = inds.len();
-        println!(
+        log::debug!(
             "Filtered {} individuals with non-finite fitness ({} remaining)",
             size_before_filter - size_after_filter,
             size_after_filter
         );
+        self.filtered = size_before_filter - size_after_filter;
         self.individuals = inds;
         self.individuals.sort_by(|a, b| a.partial_cmp(b).unwrap());
-        self.individuals.drain(1_000.min(self.individuals.len())..);
+        self.individuals
+            .drain(MAX_POPULATION_SIZE.min(self.individuals.len())..);
         self
     }
 
@@ -78,6 +199,7 @@ impl Population {
         Population {
             individuals,
             rng: self.rng,
+            filtered: 0,
         }
         .sorted()
     }
@@ -94,7 +216,22 @@ impl Population {
         Population {
             individuals,
             rng: self.rng.clone(),
+            filtered: 0,
+        }
+    }
+
+    /// Replaces the best `individuals.len()` members and sorts again.
+    pub fn replace_bests(self, individuals: Vec<Individual>) -> Population {
+        let mut all = self.individuals;
+        let n = individuals.len().min(all.len());
+        all.splice(..n, individuals);
+
+        Population {
+            individuals: all,
+            rng: self.rng,
+            filtered: 0,
         }
+        .sorted()
     }
 
     pub fn get_best(&self) -> Option<&Individual> {
@@ -123,44 +260,119 @@ impl Population {
     }
 
     pub fn from_individuals(individuals: Vec<Individual>, seed: u64) -> Self {
-        let rng = StdRng::seed_from_u64(seed);
+        let rng = ChaCha12Rng::seed_from_u64(seed);
+
+        Self {
+            individuals,
+            rng,
+            filtered: 0,
+        }
+        .sorted()
+    }
+
+    pub fn from_genomes(
+        genomes: Vec<Genome>,
+        works: usize,
+        objective: Arc<Objective>,
+        dir: Arc<str>,
+        seed: u64,
+        cache: FitnessCache,
+    ) -> Self {
+        let individuals = Self::evaluate(genomes, works, objective, dir, seed, cache);
 
-        Self { individuals, rng }.sorted()
+        Self::from_individuals(individuals, seed)
+    }
+
+    /// Scores `genomes` on `works` threads, keeping their order.
+    pub fn evaluate(
+        genomes: Vec<Genome>,
+        works: usize,
+        objective: Arc<Objective>,
+        dir: Arc<str>,
+        seed: u64,
+        cache: FitnessCache,
+    ) -> Vec<Individual> {
+        let evaluator = EvaluateGenomes::new(objective, dir, seed, cache);
+        if works == 0 {
+            work_serial(genomes, evaluator)
+        } else {
+            work_pool(works, genomes, evaluator)
+        }
     }
 }
 
+#[derive(Clone)]
+struct EvaluateGenomes {
+    objective: Arc<Objective>,
+    dir: Arc<str>,
+    seed: u64,
+    cache: FitnessCache,
+}
+
+impl EvaluateGenomes {
+    pub fn new(objective: Arc<Objective>, dir: Arc<str>, seed: u64, cache: FitnessCache) -> Self {
+        Self {
+            objective,
+            dir,
+            seed,
+            cache,
+        }
+    }
+}
+
+impl Work for EvaluateGenomes {
+    type Input = Genome;
+    type Output = Individual;
+
+    fn work(&mut self, input: Vec<Self::Input>) -> Vec<Self::Output> {
+        input
+            .into_iter()
+            .map(|genome| {
+                Individual::from_genome(
+                    genome,
+                    self.objective.clone(),
+                    self.dir.clone(),
+                    self.seed,
+                    &self.cache,
+                )
+            })
+            .collect()
+    }
+
+    fn set_start(&mut self, _start: usize) {}
+}
+
 #[derive(Clone)]
 struct NewRandomPopulation {
-    id: usize,
-    model: Model,
-    dir: &'static str,
+    start: usize,
+    objective: Arc<Objective>,
+    dir: Arc<str>,
     max_kp: f32,
     max_ki: f32,
     max_kd: f32,
-    rng: StdRng,
     seed: u64,
+    cache: FitnessCache,
 }
 
 impl NewRandomPopulation {
     pub fn new(
-        model: Model,
-        dir: &'static str,
+        objective: Arc<Objective>,
+        dir: Arc<str>,
         max_kp: f32,
         max_ki: f32,
         max_kd: f32,
         seed: u64,
+        cache: FitnessCache,
     ) -> Self {
-        let rng = StdRng::seed_from_u64(seed);
-
         Self {
-            id: 0,
-            model,
+            start: 0,
+            objective,
             dir,
             max_kp,
             max_ki,
             max_kd,
-            rng,
             seed,
+            cache,
         }
     }
 }
@@ -172,17 +384,25 @@ impl Work for NewRandomPopulation {
     fn work(&mut self, input: Vec<Self::Input>) -> Vec<Self::Output> {
         let size = input.len();
         let mut individuals = Vec::with_capacity(size);
-        for _ in 0..size {
-            let kp = self.rng.random::<f32>() * self.max_kp;
-            let ki = self.rng.random::<f32>() * self.max_ki;
-            let kd = self.rng.random::<f32>() * self.max_kd;
-            individuals.push(Individual::new(kp, ki, kd, self.model, self.dir, self.seed));
+        for i in 0..size {
+            // One stream per individual, independent of the worker split.
+            let mut rng = StdRng::seed_from_u64(stream_seed(self.seed, self.start + i));
+            let kp = rng.random::<f32>() * self.max_kp;
+            let ki = rng.random::<f32>() * self.max_ki;
+            let kd = rng.random::<f32>() * self.max_kd;
+            individuals.push(Individual::from_genome(
+                Genome::new(kp, ki, kd),
+                self.objective.clone(),
+                self.dir.clone(),
+                self.seed,
+                &self.cache,
+            ));
         }
 
         individuals
     }
 
-    fn set_id(&mut self, id: usize) {
-        self.id = id;
+    fn set_start(&mut self, start: usize) {
+        self.start = start;
     }
 }
@@ -0,0 +1,440 @@
+use std::{sync::Arc, time::Instant};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::{
+    cache::FitnessCache,
+    individual::{Genome, Individual},
+    observer::{Observer, Observers},
+    optimizer::{Optimizer, OptimizerError, check_bounds},
+    population::{MAX_POPULATION_SIZE, Population},
+    scenario::Objective,
+    stats::GenerationStats,
+};
+
+/// Inertia weight used when the builder sets none, from Clerc's constriction.
+pub const DEFAULT_INERTIA: f32 = 0.7298;
+/// Cognitive and social coefficients used when the builder sets none.
+pub const DEFAULT_ACCELERATION: f32 = 1.49618;
+
+fn gains(genome: Genome) -> [f32; 3] {
+    [genome.kp, genome.ki, genome.kd]
+}
+
+// Finite fitness beats any non-finite one.
+fn improves(candidate: &Individual, incumbent: &Individual) -> bool {
+    candidate.fitness().is_finite()
+        && (!incumbent.fitness().is_finite() || candidate.fitness() < incumbent.fitness())
+}
+
+struct Particle {
+    position: [f32; 3],
+    velocity: [f32; 3],
+    fitness: f32,
+    best: Individual,
+}
+
+/// Global-best particle swarm over PID gains, kept in the `[0, max_k*]` box.
+pub struct ParticleSwarm {
+    particles: Vec<Particle>,
+    best: Option<Individual>,
+    generation: usize,
+    swarm_size: usize,
+    parallel_works: usize,
+    objective: Arc<Objective>,
+    dir: Arc<str>,
+    bounds: [f32; 3],
+    inertia: f32,
+    cognitive: f32,
+    social: f32,
+    seed: u64,
+    rng: ChaCha12Rng,
+    cache: FitnessCache,
+    history: Vec<GenerationStats>,
+    observers: Observers,
+}
+
+/// Configures and builds a [`ParticleSwarm`], evaluating its initial positions.
+#[derive(Default)]
+pub struct ParticleSwarmBuilder {
+    swarm_size: Option<usize>,
+    parallel_works: usize,
+    objective: Objective,
+    dir: Arc<str>,
+    max_kp: Option<f32>,
+    max_ki: Option<f32>,
+    max_kd: Option<f32>,
+    inertia: Option<f32>,
+    cognitive: Option<f32>,
+    social: Option<f32>,
+    seed: u64,
+    cache: FitnessCache,
+    observers: Observers,
+}
+
+// Required builder parameters once checked.
+struct Settings {
+    swarm_size: usize,
+    bounds: [f32; 3],
+    inertia: f32,
+    cognitive: f32,
+    social: f32,
+}
+
+impl ParticleSwarmBuilder {
+    /// Number of particles.
+    pub fn with_swarm_size(mut self, size: usize) -> Self {
+        self.swarm_size = Some(size);
+        self
+    }
+
+    /// Worker threads; 0 evaluates on the calling thread.
+    pub fn with_parallel_works(mut self, works: usize) -> Self {
+        self.parallel_works = works;
+        self
+    }
+
+    /// Plant, metric and scenarios particles are scored against.
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    /// Directory under `output/` where traces of the best individual are written.
+    pub fn with_output_dir(mut self, dir: impl Into<Arc<str>>) -> Self {
+        self.dir = dir.into();
+        self
+    }
+
+    /// Upper bound of kp.
+    pub fn with_max_kp(mut self, max_kp: f32) -> Self {
+        self.max_kp = Some(max_kp);
+        self
+    }
+
+    /// Upper bound of ki.
+    pub fn with_max_ki(mut self, max_ki: f32) -> Self {
+        self.max_ki = Some(max_ki);
+        self
+    }
+
+    /// Upper bound of kd.
+    pub fn with_max_kd(mut self, max_kd: f32) -> Self {
+        self.max_kd = Some(max_kd);
+        self
+    }
+
+    /// Share of its previous velocity a particle keeps at each step.
+    pub fn with_inertia(mut self, inertia: f32) -> Self {
+        self.inertia = Some(inertia);
+        self
+    }
+
+    /// Pull towards the particle's own best position.
+    pub fn with_cognitive(mut self, cognitive: f32) -> Self {
+        self.cognitive = Some(cognitive);
+        self
+    }
+
+    /// Pull towards the best position of the swarm.
+    pub fn with_social(mut self, social: f32) -> Self {
+        self.social = Some(social);
+        self
+    }
+
+    /// Seed of every random stream.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Cache consulted before simulating a genome.
+    pub fn with_fitness_cache(mut self, cache: FitnessCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Adds an observer notified of every generation; see [`Observer`].
+    pub fn with_observer(mut self, observer: impl Observer + 'static) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    /// Checks the settings without evaluating anything.
+    pub fn validate(&self) -> Result<(), OptimizerError> {
+        self.settings().map(|_| ())
+    }
+
+    fn settings(&self) -> Result<Settings, OptimizerError> {
+        let swarm_size = self
+            .swarm_size
+            .ok_or(OptimizerError::Missing("swarm_size"))?;
+        let bounds = [
+            self.max_kp.ok_or(OptimizerError::Missing("max_kp"))?,
+            self.max_ki.ok_or(OptimizerError::Missing("max_ki"))?,
+            self.max_kd.ok_or(OptimizerError::Missing("max_kd"))?,
+        ];
+        let inertia = self.inertia.unwrap_or(DEFAULT_INERTIA);
+        let cognitive = self.cognitive.unwrap_or(DEFAULT_ACCELERATION);
+        let social = self.social.unwrap_or(DEFAULT_ACCELERATION);
+
+        if !(2..=MAX_POPULATION_SIZE).contains(&swarm_size) {
+            return Err(OptimizerError::Invalid(
+                "swarm_size",
+                format!("must be between 2 and {MAX_POPULATION_SIZE}, got {swarm_size}"),
+            ));
+        }
+        for (name, coefficient) in [
+            ("inertia", inertia),
+            ("cognitive", cognitive),
+            ("social", social),
+        ] {
+            if !(coefficient.is_finite() && coefficient >= 0.0) {
+                return Err(OptimizerError::Invalid(
+                    name,
+                    format!("must be a non-negative number, got {coefficient}"),
+                ));
+            }
+        }
+        check_bounds(bounds)?;
+
+        self.objective
+            .validate()
+            .map_err(OptimizerError::Objective)?;
+
+        Ok(Settings {
+            swarm_size,
+            bounds,
+            inertia,
+            cognitive,
+            social,
+        })
+    }
+
+    /// Evaluates the initial positions and returns the swarm at generation 0.
+    pub fn build(self) -> Result<ParticleSwarm, OptimizerError> {
+        let Settings {
+            swarm_size,
+            bounds,
+            inertia,
+            cognitive,
+            social,
+        } = self.settings()?;
+
+        let mut swarm = ParticleSwarm {
+            particles: vec![],
+            best: None,
+            generation: 0,
+            swarm_size,
+            parallel_works: self.parallel_works,
+            objective: Arc::new(self.objective),
+            dir: self.dir.clone(),
+            bounds,
+            inertia,
+            cognitive,
+            social,
+            seed: self.seed,
+            rng: ChaCha12Rng::seed_from_u64(self.seed),
+            cache: self.cache,
+            history: vec![],
+            observers: self.observers,
+        };
+        swarm.initialise()?;
+
+        Ok(swarm)
+    }
+}
+
+impl ParticleSwarm {
+    fn evaluate(&self, positions: Vec<[f32; 3]>) -> Vec<Individual> {
+        let genomes = positions
+            .into_iter()
+            .map(|[kp, ki, kd]| Genome::new(kp, ki, kd))
+            .collect();
+
+        Population::evaluate(
+            genomes,
+            self.parallel_works,
+            self.objective.clone(),
+            self.dir.clone(),
+            self.seed,
+            self.cache.clone(),
+        )
+    }
+
+    // Moves one particle and keeps it inside the bounds.
+    fn advance(&mut self, index: usize, leader: [f32; 3]) {
+        let (inertia, cognitive, social) = (self.inertia, self.cognitive, self.social);
+        let particle = &mut self.particles[index];
+        let own = gains(particle.best.genome());
+
+        for d in 0..3 {
+            let (r1, r2) = (self.rng.random::<f32>(), self.rng.random::<f32>());
+            let max = self.bounds[d];
+            let x = particle.position[d];
+            let v = inertia * particle.velocity[d]
+                + cognitive * r1 * (own[d] - x)
+                + social * r2 * (leader[d] - x);
+
+            let v = v.clamp(-max, max);
+            let x = x + v;
+            if (0.0..=max).contains(&x) {
+                particle.position[d] = x;
+                particle.velocity[d] = v;
+            } else {
+                particle.position[d] = x.clamp(0.0, max);
+                particle.velocity[d] = 0.0;
+            }
+        }
+    }
+
+    // Appends the statistics of the personal bests.
+    fn record(&mut self, evaluations: usize, filtered: usize, start: Instant) {
+        let bests = self
+            .particles
+            .iter()
+            .map(|particle| particle.best.clone())
+            .collect();
+        let population = Population::from_individuals(bests, self.seed);
+        if let Some(stats) = GenerationStats::new(self.generation, &population) {
+            let stats = stats.with_evaluations(evaluations, filtered, &self.cache, start);
+            self.history.push(stats);
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+}
+
+impl Optimizer for ParticleSwarm {
+    fn initialise(&mut self) -> Result<(), OptimizerError> {
+        let start = Instant::now();
+        self.rng = ChaCha12Rng::seed_from_u64(self.seed);
+        let positions = (0..self.swarm_size)
+            .map(|_| self.bounds.map(|max| self.rng.random::<f32>() * max))
+            .collect::<Vec<_>>();
+        let individuals = self.evaluate(positions.clone());
+
+        self.best = None;
+        for ind in &individuals {
+            if self.best.as_ref().is_none_or(|best| improves(ind, best)) {
+                self.best = Some(ind.clone());
+            }
+        }
+        let filtered = individuals
+            .iter()
+            .filter(|ind| !ind.fitness().is_finite())
+            .count();
+        self.particles = positions
+            .into_iter()
+            .zip(individuals)
+            .map(|(position, best)| Particle {
+                position,
+                velocity: [0.0; 3],
+                fitness: best.fitness(),
+                best,
+            })
+            .collect();
+        self.generation = 0;
+        self.history.clear();
+        if self
+            .best
+            .as_ref()
+            .is_none_or(|best| !best.fitness().is_finite())
+        {
+            return Err(OptimizerError::EmptyPopulation);
+        }
+        self.record(self.swarm_size, filtered, start);
+
+        Ok(())
+    }
+
+    fn step(&mut self) -> Result<Individual, OptimizerError> {
+        let leader = self.best.clone().ok_or(OptimizerError::EmptyPopulation)?;
+        let start = Instant::now();
+        self.observers.generation_start(self.generation + 1);
+
+        let leader_gains = gains(leader.genome());
+        for index in 0..self.particles.len() {
+            self.advance(index, leader_gains);
+        }
+
+        let positions = self
+            .particles
+            .iter()
+            .map(|particle| particle.position)
+            .collect::<Vec<_>>();
+        let evaluations = positions.len();
+        let individuals = self.evaluate(positions);
+
+        let mut filtered = 0;
+        let mut best = leader.clone();
+        for (particle, ind) in self.particles.iter_mut().zip(individuals) {
+            particle.fitness = ind.fitness();
+            if !ind.fitness().is_finite() {
+                filtered += 1;
+            }
+            if improves(&ind, &best) {
+                best = ind.clone();
+            }
+            if improves(&ind, &particle.best) {
+                particle.best = ind;
+            }
+        }
+        let improved = best.fitness() < leader.fitness();
+        self.best = Some(best.clone());
+
+        self.generation += 1;
+        self.record(evaluations, filtered, start);
+
+        if let Some(stats) = self.history.last() {
+            self.observers.generation_end(stats, &best, improved);
+        }
+
+        Ok(best)
+    }
+
+    fn best(&self) -> Option<Individual> {
+        self.best.clone()
+    }
+
+    fn statistics(&self) -> &[GenerationStats] {
+        &self.history
+    }
+
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Current positions with a finite fitness, best first.
+    fn genomes(&self) -> Vec<Genome> {
+        let mut particles = self
+            .particles
+            .iter()
+            .filter(|particle| particle.fitness.is_finite())
+            .collect::<Vec<_>>();
+        particles.sort_by(|a, b| a.fitness.total_cmp(&b.fitness));
+
+        particles
+            .into_iter()
+            .map(|particle| {
+                let [kp, ki, kd] = particle.position;
+                Genome::new(kp, ki, kd)
+            })
+            .collect()
+    }
+
+    fn observers(&mut self) -> &mut Observers {
+        &mut self.observers
+    }
+}
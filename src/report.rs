@@ -0,0 +1,212 @@
+use std::{
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use pid_opt::{
+    experiment::Experiment, individual::Individual, metric::Metric, stability::Margins,
+    stats::GenerationStats, tuning::Baseline,
+};
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em auto;max-width:1100px;color:#222}\
+table{border-collapse:collapse;margin:1em 0}\
+th,td{border:1px solid #ccc;padding:4px 10px;text-align:right}\
+th:first-child,td:first-child{text-align:left}\
+pre{background:#f5f5f5;padding:1em;overflow-x:auto}\
+figure{margin:1em 0}svg{max-width:100%;height:auto}";
+
+/// Everything needed to write a run's self-contained HTML report.
+pub struct Report<'a> {
+    pub run: &'a str,
+    pub experiment: &'a Experiment,
+    pub best: Option<&'a Individual>,
+    /// Classical tunings scored on the same objective, best first.
+    pub baselines: &'a [Baseline],
+    pub history: &'a [GenerationStats],
+    pub plots: &'a [PathBuf],
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn optional(value: Option<f64>, unit: &str) -> String {
+    value.map_or("n/a".to_string(), |value| format!("{value:.3} {unit}"))
+}
+
+impl Report<'_> {
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.render()?)
+    }
+
+    fn render(&self) -> io::Result<String> {
+        let mut html = String::new();
+        let title = format!("PID tuning report: {}", escape(self.run));
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{title}</title>\
+             <style>{STYLE}</style></head><body>\n<h1>{title}</h1>\n"
+        );
+
+        self.render_summary(&mut html);
+        self.render_metrics(&mut html);
+        self.render_baselines(&mut html);
+        self.render_timing(&mut html);
+
+        html.push_str("<h2>Plots</h2>\n");
+        for plot in self.plots {
+            let svg = fs::read_to_string(plot)?;
+            let name = plot
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let _ = writeln!(
+                html,
+                "<figure>{svg}<figcaption>{}</figcaption></figure>",
+                escape(&name)
+            );
+        }
+
+        let config = toml::to_string_pretty(self.experiment).map_err(io::Error::other)?;
+        let _ = write!(
+            html,
+            "<h2>Configuration</h2>\n<pre>{}</pre>\n</body></html>\n",
+            escape(&config)
+        );
+
+        Ok(html)
+    }
+
+    fn render_summary(&self, html: &mut String) {
+        let experiment = self.experiment;
+        let _ = write!(
+            html,
+            "<h2>Summary</h2>\n<table>\
+             <tr><td>Optimizer</td><td>{}</td></tr>\
+             <tr><td>Plant</td><td>{}</td></tr>\
+             <tr><td>Metric</td><td>{}</td></tr>\
+             <tr><td>Seed</td><td>{:#x}</td></tr>",
+            experiment.optimizer.name(),
+            experiment.plant.name(),
+            experiment.metric.name(),
+            experiment.seed
+        );
+
+        if let Some(best) = self.best {
+            let margins = Margins::of(experiment.plant, best.genome());
+            let _ = write!(
+                html,
+                "<tr><td>kp</td><td>{:.10}</td></tr>\
+                 <tr><td>ki</td><td>{:.10}</td></tr>\
+                 <tr><td>kd</td><td>{:.10}</td></tr>\
+                 <tr><td>Fitness</td><td>{:.10}</td></tr>\
+                 <tr><td>Gain margin</td><td>{}</td></tr>\
+                 <tr><td>Phase margin</td><td>{}</td></tr>",
+                best.kp(),
+                best.ki(),
+                best.kd(),
+                best.fitness(),
+                optional(margins.gain_margin_db, "dB"),
+                optional(margins.phase_margin_deg, "deg"),
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    fn render_metrics(&self, html: &mut String) {
+        let Some(best) = self.best else {
+            return;
+        };
+
+        html.push_str("<h2>Metrics per scenario</h2>\n<table><tr><th>Scenario</th><th>Weight</th>");
+        for metric in Metric::ALL {
+            let _ = write!(html, "<th>{}</th>", metric.name());
+        }
+        html.push_str("</tr>\n");
+
+        let objective = self.experiment.objective();
+        for scenario in Individual::eval_metrics(
+            best.kp(),
+            best.ki(),
+            best.kd(),
+            &objective,
+            self.experiment.seed,
+        ) {
+            let _ = write!(
+                html,
+                "<tr><td>{}</td><td>{:.3}</td>",
+                escape(&scenario.name),
+                scenario.weight
+            );
+            for metric in Metric::ALL {
+                let _ = write!(html, "<td>{:.6}</td>", scenario.value(metric));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+    }
+
+    fn render_baselines(&self, html: &mut String) {
+        if self.baselines.is_empty() {
+            return;
+        }
+
+        html.push_str(
+            "<h2>Classical tunings</h2>\n<table><tr><th>Rule</th><th>kp</th><th>ki</th>\
+             <th>kd</th><th>Fitness</th><th>Relative to best</th></tr>\n",
+        );
+        for baseline in self.baselines {
+            let individual = &baseline.individual;
+            let relative = self
+                .best
+                .map(|best| individual.fitness() / best.fitness())
+                .filter(|ratio| ratio.is_finite())
+                .map_or("n/a".to_string(), |ratio| format!("{ratio:.3}x"));
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{:.6}</td><td>{:.6}</td><td>{:.6}</td>\
+                 <td>{:.10}</td><td>{relative}</td></tr>",
+                escape(&baseline.tuning.name()),
+                individual.kp(),
+                individual.ki(),
+                individual.kd(),
+                individual.fitness(),
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    fn render_timing(&self, html: &mut String) {
+        let generations = self.history.last().map_or(0, |stats| stats.generation);
+        let seconds = self
+            .history
+            .iter()
+            .map(|stats| stats.elapsed_secs)
+            .sum::<f64>();
+        let evaluations = self
+            .history
+            .iter()
+            .map(|stats| stats.evaluations)
+            .sum::<usize>();
+        let hits = self
+            .history
+            .iter()
+            .map(|stats| stats.cache_hits)
+            .sum::<usize>();
+
+        let _ = write!(
+            html,
+            "<h2>Timing</h2>\n<table>\
+             <tr><td>Generations</td><td>{generations}</td></tr>\
+             <tr><td>Evaluations</td><td>{evaluations} ({hits} from cache)</td></tr>\
+             <tr><td>Optimisation time</td><td>{seconds:.2} s</td></tr>\
+             <tr><td>Time per generation</td><td>{:.3} s</td></tr>\
+             </table>\n",
+            seconds / self.history.len().max(1) as f64
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+const LATEST: &str = "latest";
+const CHECKPOINT: &str = "checkpoint.json";
+const MANIFEST: &str = "manifest.json";
+
+pub struct RunDir {
+    name: String,
+    resuming: bool,
+}
+
+#[derive(Serialize)]
+struct Manifest {
+    run: String,
+    finished_at: u64,
+    files: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize)]
+struct ManifestEntry {
+    path: String,
+    bytes: u64,
+}
+
+fn run_number(name: &str) -> Option<usize> {
+    name.strip_prefix("run-")?.parse().ok()
+}
+
+fn latest_run(base: &Path) -> Option<String> {
+    let pointer = base.join(LATEST);
+    let target = fs::read_link(&pointer)
+        .map(|target| target.display().to_string())
+        .or_else(|_| fs::read_to_string(&pointer).map(|text| text.trim().to_string()))
+        .ok()?;
+
+    base.join(&target).is_dir().then_some(target)
+}
+
+fn point_latest(base: &Path, run: &str) -> io::Result<()> {
+    let pointer = base.join(LATEST);
+    let _ = fs::remove_file(&pointer);
+
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(run, pointer);
+
+    #[cfg(not(unix))]
+    return fs::write(pointer, run);
+}
+
+impl RunDir {
+    /// Picks the run directory: the latest one to overwrite or resume, or a new one.
+    pub fn prepare(base: &str, overwrite: bool, resume: bool) -> io::Result<Self> {
+        let base_dir = Path::new("output").join(base);
+        fs::create_dir_all(&base_dir)?;
+
+        if let Some(latest) = latest_run(&base_dir) {
+            let path = base_dir.join(&latest);
+            if overwrite {
+                log::info!("Overwriting {}...", path.display());
+                fs::remove_dir_all(&path)?;
+                fs::create_dir(&path)?;
+                return Ok(Self {
+                    name: format!("{base}/{latest}"),
+                    resuming: false,
+                });
+            }
+
+            let checkpoint = path.join(CHECKPOINT).exists();
+            if resume && checkpoint {
+                return Ok(Self {
+                    name: format!("{base}/{latest}"),
+                    resuming: true,
+                });
+            }
+            if checkpoint {
+                log::info!(
+                    "Starting a new run; pass --resume to continue {}",
+                    path.display()
+                );
+            }
+        }
+        if resume {
+            log::warn!("No checkpoint to resume under {}", base_dir.display());
+        }
+
+        let next = fs::read_dir(&base_dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| run_number(&entry.file_name().to_string_lossy()))
+            .max()
+            .map_or(1, |last| last + 1);
+        let run = format!("run-{next:04}");
+        fs::create_dir(base_dir.join(&run))?;
+        point_latest(&base_dir, &run)?;
+
+        Ok(Self {
+            name: format!("{base}/{run}"),
+            resuming: false,
+        })
+    }
+
+    /// Path relative to `output/`, as expected by `with_output_dir`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> PathBuf {
+        Path::new("output").join(&self.name)
+    }
+
+    pub fn file(&self, name: &str) -> String {
+        self.path().join(name).display().to_string()
+    }
+
+    pub fn resuming(&self) -> bool {
+        self.resuming
+    }
+
+    pub fn write_manifest(&self) -> io::Result<()> {
+        let root = self.path();
+        let mut files = vec![];
+        let mut pending = vec![root.clone()];
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(dir)? {
+                let entry = entry?;
+                let path = entry.path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else if entry.file_name() != MANIFEST {
+                    files.push(ManifestEntry {
+                        path: path
+                            .strip_prefix(&root)
+                            .unwrap_or(&path)
+                            .display()
+                            .to_string(),
+                        bytes: entry.metadata()?.len(),
+                    });
+                }
+            }
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let manifest = Manifest {
+            run: self.name.clone(),
+            finished_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+            files,
+        };
+        fs::write(root.join(MANIFEST), serde_json::to_vec_pretty(&manifest)?)
+    }
+}
@@ -0,0 +1,221 @@
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::{individual::Genome, work::stream_seed};
+
+// Stream of the sampling draws, apart from the optimiser and seeding streams.
+const SAMPLING_STREAM: usize = usize::MAX - 1;
+// Halton bases, one prime per gain.
+const HALTON_BASES: [u32; 3] = [2, 3, 5];
+// Joe–Kuo polynomials and direction numbers of the second and third gains.
+const SOBOL_POLYNOMIALS: [(usize, u32, &[u32]); 2] = [(1, 0, &[1]), (2, 1, &[1, 3])];
+
+/// How the random part of an initial population covers `[0, max_k*]`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sampling {
+    /// Independent uniform draws for every gain.
+    #[default]
+    Uniform,
+    /// One point in each of `size` equal slices of every gain.
+    LatinHypercube,
+    /// Halton sequence in bases 2, 3 and 5, randomly shifted.
+    Halton,
+    /// Sobol sequence, digitally shifted at random.
+    Sobol,
+}
+
+impl Sampling {
+    /// `size` genomes with gains in `[0, bound]` for `[max_kp, max_ki, max_kd]`.
+    pub fn sample(&self, size: usize, bounds: [f32; 3], seed: u64) -> Vec<Genome> {
+        let mut rng = StdRng::seed_from_u64(stream_seed(seed, SAMPLING_STREAM));
+        let points: Vec<[f64; 3]> = match self {
+            Sampling::Uniform => (0..size)
+                .map(|_| std::array::from_fn(|_| rng.random::<f64>()))
+                .collect(),
+            Sampling::LatinHypercube => latin_hypercube(size, &mut rng),
+            Sampling::Halton => {
+                let shifts: [f64; 3] = std::array::from_fn(|_| rng.random());
+                (1..=size)
+                    .map(|i| {
+                        std::array::from_fn(|d| {
+                            (radical_inverse(i as u32, HALTON_BASES[d]) + shifts[d]).fract()
+                        })
+                    })
+                    .collect()
+            }
+            Sampling::Sobol => {
+                let shifts: [u32; 3] = std::array::from_fn(|_| rng.random());
+                sobol(size)
+                    .into_iter()
+                    .map(|point| {
+                        std::array::from_fn(|d| (point[d] ^ shifts[d]) as f64 / 2f64.powi(32))
+                    })
+                    .collect()
+            }
+        };
+
+        points
+            .into_iter()
+            .map(|[kp, ki, kd]| {
+                Genome::new(
+                    kp as f32 * bounds[0],
+                    ki as f32 * bounds[1],
+                    kd as f32 * bounds[2],
+                )
+            })
+            .collect()
+    }
+}
+
+fn latin_hypercube(size: usize, rng: &mut impl Rng) -> Vec<[f64; 3]> {
+    let strata: [Vec<usize>; 3] = std::array::from_fn(|_| {
+        let mut stratum: Vec<usize> = (0..size).collect();
+        stratum.shuffle(rng);
+        stratum
+    });
+
+    (0..size)
+        .map(|i| std::array::from_fn(|d| (strata[d][i] as f64 + rng.random::<f64>()) / size as f64))
+        .collect()
+}
+
+// Reflects the digits of `i` in `base` about the radix point.
+fn radical_inverse(mut i: u32, base: u32) -> f64 {
+    let mut inverse = 0.0;
+    let mut scale = 1.0 / base as f64;
+    while i > 0 {
+        inverse += (i % base) as f64 * scale;
+        i /= base;
+        scale /= base as f64;
+    }
+
+    inverse
+}
+
+// First `size` Sobol points after the origin, as 32-bit fractions.
+fn sobol(size: usize) -> Vec<[u32; 3]> {
+    let mut directions = [[0u32; 32]; 3];
+    for (k, direction) in directions[0].iter_mut().enumerate() {
+        *direction = 1 << (31 - k);
+    }
+    for (directions, &(degree, coefficients, initial)) in
+        directions[1..].iter_mut().zip(&SOBOL_POLYNOMIALS)
+    {
+        let mut m = initial.to_vec();
+        for k in degree..32 {
+            let mut next = m[k - degree] ^ (m[k - degree] << degree);
+            for j in 1..degree {
+                if (coefficients >> (degree - 1 - j)) & 1 == 1 {
+                    next ^= m[k - j] << j;
+                }
+            }
+            m.push(next);
+        }
+        for (k, direction) in directions.iter_mut().enumerate() {
+            *direction = m[k] << (31 - k);
+        }
+    }
+
+    let mut point = [0u32; 3];
+    (0..size)
+        .map(|i| {
+            let bit = (!i).trailing_zeros() as usize;
+            for (coordinate, directions) in point.iter_mut().zip(&directions) {
+                *coordinate ^= directions[bit];
+            }
+            point
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METHODS: [Sampling; 4] = [
+        Sampling::Uniform,
+        Sampling::LatinHypercube,
+        Sampling::Halton,
+        Sampling::Sobol,
+    ];
+
+    // Index of the slice of `[0, 1)` holding `x`, out of `slices`.
+    fn slice(x: f64, slices: usize) -> usize {
+        (x * slices as f64) as usize
+    }
+
+    #[test]
+    fn radical_inverse_reflects_digits() {
+        for (i, base, expected) in [
+            (1, 2, 0.5),
+            (2, 2, 0.25),
+            (3, 2, 0.75),
+            (6, 2, 0.375),
+            (1, 3, 1.0 / 3.0),
+            (5, 3, 7.0 / 9.0),
+            (7, 5, 0.4 + 0.04),
+        ] {
+            assert!((radical_inverse(i, base) - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn sobol_fills_every_slice_once() {
+        let mut points = vec![[0u32; 3]];
+        points.extend(sobol(15));
+        assert_eq!(points[1], [1 << 31; 3]);
+        for d in 0..3 {
+            let mut slices: Vec<u32> = points.iter().map(|point| point[d] >> 28).collect();
+            slices.sort_unstable();
+            assert_eq!(slices, (0..16).collect::<Vec<_>>(), "gain {d}");
+        }
+    }
+
+    #[test]
+    fn latin_hypercube_fills_every_slice_once() {
+        let size = 25;
+        let points = latin_hypercube(size, &mut StdRng::seed_from_u64(7));
+        for d in 0..3 {
+            let mut slices: Vec<usize> = points.iter().map(|point| slice(point[d], size)).collect();
+            slices.sort_unstable();
+            assert_eq!(slices, (0..size).collect::<Vec<_>>(), "gain {d}");
+        }
+    }
+
+    #[test]
+    fn samples_stay_in_bounds_and_repeat_per_seed() {
+        let bounds = [10.0, 2.0, 0.0];
+        for method in METHODS {
+            let genomes = method.sample(50, bounds, 3);
+            assert_eq!(genomes.len(), 50);
+            for genome in &genomes {
+                assert!((0.0..=bounds[0]).contains(&genome.kp), "{method:?}");
+                assert!((0.0..=bounds[1]).contains(&genome.ki), "{method:?}");
+                assert_eq!(genome.kd, 0.0, "{method:?}");
+            }
+
+            let bits = |genomes: &[Genome]| -> Vec<[u32; 2]> {
+                genomes
+                    .iter()
+                    .map(|genome| [genome.kp.to_bits(), genome.ki.to_bits()])
+                    .collect()
+            };
+            assert_eq!(bits(&genomes), bits(&method.sample(50, bounds, 3)));
+            assert_ne!(bits(&genomes), bits(&method.sample(50, bounds, 4)));
+        }
+    }
+
+    #[test]
+    fn halton_shift_keeps_the_sequence_spacing() {
+        let genomes = Sampling::Halton.sample(8, [1.0; 3], 11);
+        let mut slices: Vec<usize> = genomes
+            .iter()
+            .map(|genome| slice(genome.kp as f64, 8))
+            .collect();
+        slices.sort_unstable();
+        slices.dedup();
+        // The eighth point is off the 1/8 lattice, so a shift can move it into a taken slice.
+        assert!(slices.len() >= 7, "{slices:?}");
+    }
+}
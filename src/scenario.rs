@@ -0,0 +1,327 @@
+use std::{collections::HashSet, f32::consts::PI};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    individual::{InputBlock, Model},
+    input,
+    metric::Metric,
+    stability::Screening,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Scenario {
+    Step {
+        #[serde(default = "default_amplitude")]
+        amplitude: f32,
+    },
+    Sinusoidal {
+        #[serde(default = "default_period")]
+        period: f32,
+        #[serde(default = "default_amplitude")]
+        amplitude: f32,
+        #[serde(default)]
+        offset: f32,
+    },
+    Square {
+        #[serde(default = "default_period")]
+        period: f32,
+        #[serde(default = "default_amplitude")]
+        amplitude: f32,
+        #[serde(default)]
+        offset: f32,
+    },
+    Sawtooth {
+        #[serde(default = "default_period")]
+        period: f32,
+        #[serde(default = "default_amplitude")]
+        amplitude: f32,
+        #[serde(default)]
+        offset: f32,
+    },
+    Random {
+        #[serde(default)]
+        min_amplitude: f32,
+        #[serde(default = "default_amplitude")]
+        max_amplitude: f32,
+        #[serde(default = "default_period")]
+        min_period: f32,
+        #[serde(default = "default_max_period")]
+        max_period: f32,
+    },
+}
+
+fn default_amplitude() -> f32 {
+    1.0
+}
+
+fn default_period() -> f32 {
+    2.0 * PI
+}
+
+fn default_max_period() -> f32 {
+    2.5 * PI
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+fn default_dt() -> f32 {
+    1e-2
+}
+
+fn default_duration() -> f32 {
+    8.0 * PI
+}
+
+impl Scenario {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scenario::Step { .. } => "step",
+            Scenario::Sinusoidal { .. } => "sinusoidal",
+            Scenario::Square { .. } => "square",
+            Scenario::Sawtooth { .. } => "sawtooth",
+            Scenario::Random { .. } => "random",
+        }
+    }
+
+    pub fn block(&self, seed: u64) -> Box<InputBlock> {
+        match *self {
+            Scenario::Step { amplitude } => Box::new(input::Step::new(amplitude)),
+            Scenario::Sinusoidal {
+                period,
+                amplitude,
+                offset,
+            } => Box::new(input::Sinusoidal::new(period, amplitude, offset)),
+            Scenario::Square {
+                period,
+                amplitude,
+                offset,
+            } => Box::new(input::Square::new(period, amplitude, offset)),
+            Scenario::Sawtooth {
+                period,
+                amplitude,
+                offset,
+            } => Box::new(input::Sawtooth::new(period, amplitude, offset)),
+            Scenario::Random {
+                min_amplitude,
+                max_amplitude,
+                min_period,
+                max_period,
+            } => Box::new(input::Random::new(
+                min_amplitude,
+                max_amplitude,
+                min_period,
+                max_period,
+                seed,
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WeightedScenario {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    #[serde(flatten)]
+    pub scenario: Scenario,
+}
+
+impl WeightedScenario {
+    pub fn new(scenario: Scenario, weight: f32) -> Self {
+        Self {
+            name: None,
+            weight,
+            scenario,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(self.scenario.name())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Objective {
+    pub model: Model,
+    #[serde(default)]
+    pub metric: Metric,
+    #[serde(default = "default_dt")]
+    pub dt: f32,
+    #[serde(default = "default_duration")]
+    pub duration: f32,
+    pub scenarios: Vec<WeightedScenario>,
+    /// Skipped when unset so existing fingerprints stay valid.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disturbance: Option<Disturbance>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub noise: f32,
+    #[serde(default, skip_serializing_if = "Screening::is_off")]
+    pub screening: Screening,
+    /// Seed of the random scenarios and the noise; the optimiser's when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+/// Load step added at the plant input from `start` seconds on.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Disturbance {
+    pub amplitude: f32,
+    #[serde(default)]
+    pub start: f32,
+}
+
+impl Disturbance {
+    pub fn at(&self, t: f32) -> f32 {
+        if t >= self.start { self.amplitude } else { 0.0 }
+    }
+}
+
+fn is_zero(value: &f32) -> bool {
+    *value == 0.0
+}
+
+impl Default for Objective {
+    fn default() -> Self {
+        Self {
+            model: Model::default(),
+            metric: Metric::default(),
+            dt: default_dt(),
+            duration: default_duration(),
+            scenarios: vec![
+                WeightedScenario::new(Scenario::Step { amplitude: 1.0 }, 0.0),
+                WeightedScenario::new(
+                    Scenario::Sinusoidal {
+                        period: 2.0 * PI,
+                        amplitude: 1.0,
+                        offset: 0.0,
+                    },
+                    0.0,
+                ),
+                WeightedScenario::new(
+                    Scenario::Square {
+                        period: 2.0 * PI,
+                        amplitude: 1.0,
+                        offset: 0.0,
+                    },
+                    1.0,
+                ),
+                WeightedScenario::new(
+                    Scenario::Sawtooth {
+                        period: 2.0 * PI,
+                        amplitude: 1.0,
+                        offset: 0.0,
+                    },
+                    0.0,
+                ),
+                WeightedScenario::new(
+                    Scenario::Random {
+                        min_amplitude: 0.0,
+                        max_amplitude: 1.0,
+                        min_period: 2.0 * PI,
+                        max_period: 2.5 * PI,
+                    },
+                    0.0,
+                ),
+            ],
+            disturbance: None,
+            noise: 0.0,
+            screening: Screening::Off,
+            seed: None,
+        }
+    }
+}
+
+impl Objective {
+    /// Checks the simulation settings and scenarios.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.dt.is_finite() && self.dt > 0.0) {
+            return Err("dt must be positive".to_string());
+        }
+        if !(self.duration.is_finite() && self.duration > self.dt) {
+            return Err("duration must be greater than dt".to_string());
+        }
+
+        if let Some(disturbance) = self.disturbance {
+            if !disturbance.amplitude.is_finite() {
+                return Err("disturbance.amplitude must be a number".to_string());
+            }
+            if !(disturbance.start >= 0.0 && disturbance.start < self.duration) {
+                return Err("disturbance.start must be within the simulation".to_string());
+            }
+        }
+        if !(self.noise.is_finite() && self.noise >= 0.0) {
+            return Err("noise must be a non-negative number".to_string());
+        }
+        if matches!(self.screening, Screening::Penalty(penalty) if !penalty.is_finite()) {
+            return Err("screening.penalty must be a number".to_string());
+        }
+        if self.scenarios.is_empty() {
+            return Err("at least one scenario is required".to_string());
+        }
+        let mut names = HashSet::new();
+        for scenario in &self.scenarios {
+            let name = scenario.name();
+            if !names.insert(name) {
+                return Err(format!("scenario `{name}` is defined more than once"));
+            }
+            if !(scenario.weight.is_finite() && scenario.weight >= 0.0) {
+                return Err(format!("scenario `{name}` weight must be non-negative"));
+            }
+            validate_scenario(&scenario.scenario)
+                .map_err(|msg| format!("scenario `{name}`: {msg}"))?;
+        }
+        if self.scenarios.iter().all(|scenario| scenario.weight == 0.0) {
+            return Err("at least one scenario must have a positive weight".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Seed of the scenarios and noise for an optimiser seeded with `seed`.
+    pub fn evaluation_seed(&self, seed: u64) -> u64 {
+        self.seed.unwrap_or(seed)
+    }
+
+    /// FNV-1a of the serialised objective, stable across runs.
+    pub fn fingerprint(&self) -> u64 {
+        let serialised = serde_json::to_vec(self).unwrap_or_default();
+        serialised.iter().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+}
+
+fn validate_scenario(scenario: &Scenario) -> Result<(), String> {
+    match *scenario {
+        Scenario::Step { .. } => Ok(()),
+        Scenario::Sinusoidal { period, .. }
+        | Scenario::Square { period, .. }
+        | Scenario::Sawtooth { period, .. } => {
+            if period > 0.0 {
+                Ok(())
+            } else {
+                Err("period must be positive".to_string())
+            }
+        }
+        Scenario::Random {
+            min_amplitude,
+            max_amplitude,
+            min_period,
+            max_period,
+        } => {
+            if min_amplitude > max_amplitude {
+                Err("min_amplitude must not exceed max_amplitude".to_string())
+            } else if !(min_period > 0.0 && min_period <= max_period) {
+                Err("periods must satisfy 0 < min_period <= max_period".to_string())
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
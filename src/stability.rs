@@ -0,0 +1,310 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::individual::{Genome, Model};
+
+const MIN_FREQUENCY: f64 = 1e-3;
+const MAX_FREQUENCY: f64 = 1e4;
+const FREQUENCY_POINTS: usize = 4000;
+
+#[derive(Clone, Copy)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn norm(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    fn arg(&self) -> f64 {
+        self.im.atan2(self.re)
+    }
+}
+
+// Evaluates a polynomial, highest power first, at s = jw.
+fn eval_at(coefficients: &[f32], w: f64) -> Complex {
+    coefficients
+        .iter()
+        .fold(Complex { re: 0.0, im: 0.0 }, |acc, &c| Complex {
+            re: -acc.im * w + c as f64,
+            im: acc.re * w,
+        })
+}
+
+/// Gain and phase margins of the open loop, `None` outside the scanned band.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct Margins {
+    pub gain_margin_db: Option<f64>,
+    pub phase_crossover: Option<f64>,
+    pub phase_margin_deg: Option<f64>,
+    pub gain_crossover: Option<f64>,
+}
+
+impl Margins {
+    pub fn of(model: Model, genome: Genome) -> Self {
+        let (numerator, denominator) = model.transfer_function();
+        // PID as (kd s^2 + kp s + ki) / s.
+        let pid = [genome.kd, genome.kp, genome.ki];
+        let dead_time = model.dead_time() as f64;
+
+        let step = (MAX_FREQUENCY / MIN_FREQUENCY).ln() / (FREQUENCY_POINTS - 1) as f64;
+        let mut previous: Option<(f64, f64, f64)> = None;
+        let mut margins = Margins::default();
+
+        for i in 0..FREQUENCY_POINTS {
+            let w = MIN_FREQUENCY * (step * i as f64).exp();
+            let (num, den, controller) = (
+                eval_at(numerator, w),
+                eval_at(denominator, w),
+                eval_at(&pid, w),
+            );
+            let magnitude = num.norm() * controller.norm() / (den.norm() * w);
+            let mut rational = num.arg() + controller.arg() - den.arg() - PI / 2.0;
+
+            if let Some((prev_w, prev_magnitude, prev_rational)) = previous {
+                // Unwrapped before adding the dead time, whose lag can exceed pi.
+                while rational - prev_rational > PI {
+                    rational -= 2.0 * PI;
+                }
+                while rational - prev_rational < -PI {
+                    rational += 2.0 * PI;
+                }
+                let prev_phase = prev_rational - prev_w * dead_time;
+                let phase = rational - w * dead_time;
+
+                let interpolate = |a: f64, b: f64, target: f64| {
+                    let t = (target - a) / (b - a);
+                    let w = (prev_w.ln() + t * (w.ln() - prev_w.ln())).exp();
+                    (t, w)
+                };
+
+                if margins.gain_crossover.is_none()
+                    && (prev_magnitude - 1.0) * (magnitude - 1.0) <= 0.0
+                    && prev_magnitude != magnitude
+                {
+                    let (t, w) = interpolate(prev_magnitude.ln(), magnitude.ln(), 0.0);
+                    let phase = prev_phase + t * (phase - prev_phase);
+                    margins.gain_crossover = Some(w);
+                    margins.phase_margin_deg = Some(180.0 + phase.to_degrees());
+                }
+
+                if margins.phase_crossover.is_none()
+                    && (prev_phase + PI) * (phase + PI) <= 0.0
+                    && prev_phase != phase
+                {
+                    let (t, w) = interpolate(prev_phase, phase, -PI);
+                    let magnitude =
+                        (prev_magnitude.ln() + t * (magnitude.ln() - prev_magnitude.ln())).exp();
+                    margins.phase_crossover = Some(w);
+                    margins.gain_margin_db = Some(-20.0 * magnitude.log10());
+                }
+            }
+
+            previous = Some((w, magnitude, rational));
+        }
+
+        margins
+    }
+}
+
+// Product of two polynomials, highest power first.
+fn multiply(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut product = vec![0.0; a.len() + b.len() - 1];
+    for (i, a) in a.iter().enumerate() {
+        for (j, b) in b.iter().enumerate() {
+            product[i + j] += a * b;
+        }
+    }
+
+    product
+}
+
+/// Closed-loop characteristic polynomial, with a (2, 2) Padé dead time.
+pub fn characteristic_polynomial(model: Model, genome: Genome) -> Vec<f64> {
+    let (numerator, denominator) = model.transfer_function();
+    let to_f64 = |polynomial: &[f32]| polynomial.iter().map(|&c| c as f64).collect::<Vec<_>>();
+    let (mut numerator, mut denominator) = (to_f64(numerator), to_f64(denominator));
+    let dead_time = model.dead_time() as f64;
+    if dead_time > 0.0 {
+        let square = dead_time * dead_time / 12.0;
+        numerator = multiply(&numerator, &[square, -dead_time / 2.0, 1.0]);
+        denominator = multiply(&denominator, &[square, dead_time / 2.0, 1.0]);
+    }
+    let (pid, integrator) = if genome.ki == 0.0 {
+        (vec![genome.kd as f64, genome.kp as f64], vec![1.0])
+    } else {
+        (
+            vec![genome.kd as f64, genome.kp as f64, genome.ki as f64],
+            vec![1.0, 0.0],
+        )
+    };
+
+    let open = multiply(&integrator, &denominator);
+    let closed = multiply(&pid, &numerator);
+    let len = open.len().max(closed.len());
+    (0..len)
+        .map(|i| {
+            let coefficient = |polynomial: &[f64]| {
+                (i + polynomial.len())
+                    .checked_sub(len)
+                    .map_or(0.0, |j| polynomial[j])
+            };
+            coefficient(&open) + coefficient(&closed)
+        })
+        .collect()
+}
+
+/// Routh–Hurwitz test of `polynomial`, highest power first.
+pub fn is_hurwitz(polynomial: &[f64]) -> bool {
+    let scale = polynomial.iter().fold(0.0f64, |max, c| max.max(c.abs()));
+    let tolerance = 1e-12 * scale;
+    let Some(start) = polynomial.iter().position(|c| c.abs() > tolerance) else {
+        return false;
+    };
+    let sign = polynomial[start].signum();
+    let polynomial: Vec<f64> = polynomial[start..].iter().map(|c| c * sign).collect();
+    if polynomial.len() == 1 {
+        return true;
+    }
+    // Every coefficient positive is necessary, and enough up to degree 2.
+    if polynomial.iter().any(|&c| c <= tolerance) {
+        return false;
+    }
+
+    let width = polynomial.len().div_ceil(2);
+    let row = |offset: usize| -> Vec<f64> {
+        (0..width)
+            .map(|j| polynomial.get(2 * j + offset).copied().unwrap_or(0.0))
+            .collect()
+    };
+    let (mut upper, mut lower) = (row(0), row(1));
+    for _ in 2..polynomial.len() {
+        if lower[0] <= tolerance {
+            return false;
+        }
+        let next = (0..width)
+            .map(|j| {
+                let at = |row: &[f64]| row.get(j + 1).copied().unwrap_or(0.0);
+                at(&upper) - upper[0] / lower[0] * at(&lower)
+            })
+            .collect();
+        upper = std::mem::replace(&mut lower, next);
+    }
+
+    lower[0] > tolerance
+}
+
+/// Whether the closed loop of `genome` around `model` is stable.
+pub fn is_stable(model: Model, genome: Genome) -> bool {
+    is_hurwitz(&characteristic_polynomial(model, genome))
+}
+
+/// Stability check run on candidate gains before simulating them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Screening {
+    /// Every candidate is simulated.
+    #[default]
+    Off,
+    /// Unstable candidates get an infinite fitness and are discarded.
+    Reject,
+    /// Unstable candidates get this fitness, keeping them in the population.
+    Penalty(f32),
+}
+
+impl Screening {
+    pub fn is_off(&self) -> bool {
+        *self == Screening::Off
+    }
+
+    /// Fitness of a screened-out `genome`, `None` when it has to be simulated.
+    pub fn screen(&self, model: Model, genome: Genome) -> Option<f32> {
+        let fitness = match *self {
+            Screening::Off => return None,
+            Screening::Reject => f32::INFINITY,
+            Screening::Penalty(penalty) => penalty,
+        };
+
+        (!is_stable(model, genome)).then_some(fitness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routh_hurwitz_matches_known_roots() {
+        // (s + 1)(s + 2), and (s - 1)(s - 2).
+        assert!(is_hurwitz(&[1.0, 3.0, 2.0]));
+        assert!(!is_hurwitz(&[1.0, -3.0, 2.0]));
+        // (s + 1)(s^2 + 1) has roots on the imaginary axis.
+        assert!(!is_hurwitz(&[1.0, 1.0, 1.0, 1.0]));
+        assert!(is_hurwitz(&[1.0, 2.0, 3.0, 1.0]));
+        // All coefficients positive, yet a0 a3 > a1 a2.
+        assert!(!is_hurwitz(&[1.0, 1.0, 2.0, 8.0]));
+        assert!(is_hurwitz(&[-1.0, -3.0, -2.0]));
+        assert!(!is_hurwitz(&[0.0, 0.0]));
+    }
+
+    #[test]
+    fn high_order_lag_is_stable_below_the_ultimate_gain() {
+        // (s + 1)^4 + kp loses stability at kp = 4.
+        assert!(is_stable(Model::HighOrderLag, Genome::new(3.9, 0.0, 0.0)));
+        assert!(!is_stable(Model::HighOrderLag, Genome::new(4.1, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn dead_time_uses_the_pade_approximation() {
+        let kp = 0.8;
+        let polynomial = characteristic_polynomial(Model::DeadTime, Genome::new(kp, 0.0, 0.0));
+        // (0.5 s + 1)(s^2 / 12 + s / 2 + 1) + kp (s^2 / 12 - s / 2 + 1).
+        let open = multiply(&[0.5, 1.0], &[1.0 / 12.0, 0.5, 1.0]);
+        let closed = multiply(&[kp as f64], &[1.0 / 12.0, -0.5, 1.0]);
+        let expected = [
+            open[0],
+            open[1] + closed[0],
+            open[2] + closed[1],
+            open[3] + closed[2],
+        ];
+        assert_eq!(polynomial.len(), expected.len());
+        for (actual, expected) in polynomial.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-6, "{polynomial:?}");
+        }
+
+        // Ultimate gain about 1.52 with the exact delay, 1.54 with the Padé one.
+        assert!(is_stable(Model::DeadTime, Genome::new(1.4, 0.0, 0.0)));
+        assert!(!is_stable(Model::DeadTime, Genome::new(1.7, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn margins_of_high_order_lag() {
+        let margins = Margins::of(Model::HighOrderLag, Genome::new(1.0, 0.0, 0.0));
+        let gain_margin = margins.gain_margin_db.unwrap();
+        let phase_crossover = margins.phase_crossover.unwrap();
+        assert!(
+            (gain_margin - 20.0 * 4f64.log10()).abs() < 1e-2,
+            "{gain_margin}"
+        );
+        assert!((phase_crossover - 1.0).abs() < 1e-3, "{phase_crossover}");
+    }
+
+    #[test]
+    fn screening_only_replaces_unstable_gains() {
+        let stable = Genome::new(1.0, 0.0, 0.0);
+        let unstable = Genome::new(10.0, 0.0, 0.0);
+        assert_eq!(Screening::Off.screen(Model::HighOrderLag, unstable), None);
+        assert_eq!(Screening::Reject.screen(Model::HighOrderLag, stable), None);
+        assert_eq!(
+            Screening::Reject.screen(Model::HighOrderLag, unstable),
+            Some(f32::INFINITY)
+        );
+        assert_eq!(
+            Screening::Penalty(1e3).screen(Model::HighOrderLag, unstable),
+            Some(1e3)
+        );
+    }
+}
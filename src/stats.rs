@@ -0,0 +1,123 @@
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{cache::FitnessCache, individual::Individual, population::Population};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GenerationStats {
+    pub generation: usize,
+    pub size: usize,
+    pub best: f32,
+    pub mean: f32,
+    pub median: f32,
+    pub worst: f32,
+    pub best_kp: f32,
+    pub best_ki: f32,
+    pub best_kd: f32,
+    /// Mean over the three gains of their standard deviation in the population.
+    pub diversity: f32,
+    /// Individuals with a non-finite fitness dropped after simulation.
+    pub filtered: usize,
+    /// Candidates found unstable before simulation; not counted as filtered.
+    #[serde(default)]
+    pub screened: usize,
+    pub evaluations: usize,
+    pub cache_hits: usize,
+    pub cache_misses: usize,
+    pub cache_entries: usize,
+    pub elapsed_secs: f64,
+}
+
+fn std_dev(values: impl Iterator<Item = f32> + Clone) -> f32 {
+    let n = values.clone().count() as f32;
+    let mean = values.clone().sum::<f32>() / n;
+    (values.map(|v| (v - mean) * (v - mean)).sum::<f32>() / n).sqrt()
+}
+
+impl GenerationStats {
+    /// Fitness and diversity of a sorted population, with zeroed counters.
+    pub fn new(generation: usize, population: &Population) -> Option<Self> {
+        let individuals = population.individuals();
+        let best = individuals.first()?;
+        let worst = individuals.last()?;
+        let n = individuals.len();
+        let fitness = |i: usize| individuals[i].fitness();
+        let median = if n.is_multiple_of(2) {
+            (fitness(n / 2 - 1) + fitness(n / 2)) / 2.0
+        } else {
+            fitness(n / 2)
+        };
+
+        let genomes = individuals.iter().map(|ind| ind.genome());
+        let diversity = (std_dev(genomes.clone().map(|g| g.kp))
+            + std_dev(genomes.clone().map(|g| g.ki))
+            + std_dev(genomes.map(|g| g.kd)))
+            / 3.0;
+
+        Some(Self {
+            generation,
+            size: n,
+            best: best.fitness(),
+            mean: individuals.iter().map(|ind| ind.fitness()).sum::<f32>() / n as f32,
+            median,
+            worst: worst.fitness(),
+            best_kp: best.kp(),
+            best_ki: best.ki(),
+            best_kd: best.kd(),
+            diversity,
+            filtered: 0,
+            screened: 0,
+            evaluations: 0,
+            cache_hits: 0,
+            cache_misses: 0,
+            cache_entries: 0,
+            elapsed_secs: 0.0,
+        })
+    }
+
+    /// Statistics of a generation without any finite fitness, repeating `best`.
+    pub fn empty(generation: usize, best: Option<&Individual>) -> Self {
+        let fitness = best.map_or(f32::INFINITY, |best| best.fitness());
+
+        Self {
+            generation,
+            size: 0,
+            best: fitness,
+            mean: fitness,
+            median: fitness,
+            worst: fitness,
+            best_kp: best.map_or(0.0, |best| best.kp()),
+            best_ki: best.map_or(0.0, |best| best.ki()),
+            best_kd: best.map_or(0.0, |best| best.kd()),
+            diversity: 0.0,
+            filtered: 0,
+            screened: 0,
+            evaluations: 0,
+            cache_hits: 0,
+            cache_misses: 0,
+            cache_entries: 0,
+            elapsed_secs: 0.0,
+        }
+    }
+
+    /// Fills in the evaluation, cache and screening counters since `start`.
+    pub fn with_evaluations(
+        mut self,
+        evaluations: usize,
+        filtered: usize,
+        cache: &FitnessCache,
+        start: Instant,
+    ) -> Self {
+        let (hits, misses) = cache.take_stats();
+        let (screened, rejected) = cache.take_screened();
+        self.evaluations = evaluations;
+        self.filtered = filtered.saturating_sub(rejected);
+        self.screened = screened;
+        self.cache_hits = hits;
+        self.cache_misses = misses;
+        self.cache_entries = cache.len();
+        self.elapsed_secs = start.elapsed().as_secs_f64();
+        self
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{individual::Genome, scenario::Objective};
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TraceOptions {
+    /// Adds the P, I and D terms, disturbance and noise columns.
+    #[serde(default)]
+    pub detailed: bool,
+    /// Keeps one sample out of every `decimate`.
+    #[serde(default = "default_decimate")]
+    pub decimate: usize,
+}
+
+fn default_decimate() -> usize {
+    1
+}
+
+impl Default for TraceOptions {
+    fn default() -> Self {
+        Self {
+            detailed: false,
+            decimate: default_decimate(),
+        }
+    }
+}
+
+pub struct TraceSample {
+    pub t: f32,
+    pub input: f32,
+    pub output: f32,
+    pub control: f32,
+    pub error: f32,
+    pub terms: [f32; 3],
+    pub disturbance: f32,
+    pub noise: f32,
+}
+
+/// CSV of one scenario's simulation, after `#` lines describing the run.
+pub struct TraceWriter {
+    file: BufWriter<File>,
+    options: TraceOptions,
+    samples: usize,
+}
+
+impl TraceWriter {
+    pub fn create(
+        path: &Path,
+        scenario: &str,
+        genome: Genome,
+        objective: &Objective,
+        options: TraceOptions,
+    ) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = BufWriter::new(File::create(path)?);
+
+        writeln!(
+            file,
+            "# scenario: {scenario}, plant: {}, metric: {}",
+            objective.model.name(),
+            objective.metric.name()
+        )?;
+        writeln!(
+            file,
+            "# gains: kp = {}, ki = {}, kd = {}",
+            genome.kp, genome.ki, genome.kd
+        )?;
+        writeln!(
+            file,
+            "# sampling: dt = {} s, every {} step(s)",
+            objective.dt,
+            options.decimate.max(1)
+        )?;
+        writeln!(
+            file,
+            "# units: t in s; input, output, error and noise in plant output units; \
+             control, p, i, d and disturbance in plant input units"
+        )?;
+
+        let mut header = "t,input,output,control,error".to_string();
+        if options.detailed {
+            header.push_str(",p,i,d,disturbance,noise");
+        }
+        writeln!(file, "{header}")?;
+
+        Ok(Self {
+            file,
+            options,
+            samples: 0,
+        })
+    }
+
+    pub fn detailed(&self) -> bool {
+        self.options.detailed
+    }
+
+    pub fn write(&mut self, sample: &TraceSample) -> io::Result<()> {
+        let keep = self.samples.is_multiple_of(self.options.decimate.max(1));
+        self.samples += 1;
+        if !keep {
+            return Ok(());
+        }
+
+        write!(
+            self.file,
+            "{},{},{},{},{}",
+            sample.t, sample.input, sample.output, sample.control, sample.error
+        )?;
+        if self.options.detailed {
+            let [p, i, d] = sample.terms;
+            write!(
+                self.file,
+                ",{},{},{},{},{}",
+                p, i, d, sample.disturbance, sample.noise
+            )?;
+        }
+        writeln!(self.file)
+    }
+}
@@ -0,0 +1,388 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cache::FitnessCache,
+    individual::{Genome, Individual, Model},
+    population::Population,
+    scenario::Objective,
+    stability::Margins,
+};
+
+// Steps of the step response per unit of the plant's residence time.
+const STEPS_PER_TIME_SCALE: f64 = 2000.0;
+const MAX_STEPS: usize = 1_000_000;
+// Smallest dead time of a fit, relative to its time constant.
+const MIN_DEAD_TIME_RATIO: f64 = 0.1;
+
+/// Textbook tuning rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// Ziegler–Nichols from the ultimate gain and period.
+    ZieglerNichols,
+    /// Ziegler–Nichols reaction curve, from the FOPDT fit.
+    ZieglerNicholsStep,
+    /// Cohen–Coon, from the FOPDT fit.
+    CohenCoon,
+    /// Skogestad's SIMC PI rule from the FOPDT fit.
+    Simc,
+    /// Åström–Hägglund AMIGO, from the FOPDT fit.
+    Amigo,
+}
+
+impl Rule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::ZieglerNichols => "Ziegler-Nichols",
+            Rule::ZieglerNicholsStep => "Ziegler-Nichols step",
+            Rule::CohenCoon => "Cohen-Coon",
+            Rule::Simc => "SIMC",
+            Rule::Amigo => "AMIGO",
+        }
+    }
+}
+
+/// Controller structure a rule was applied for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Form {
+    Pi,
+    Pid,
+}
+
+impl Form {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Form::Pi => "PI",
+            Form::Pid => "PID",
+        }
+    }
+}
+
+/// Gains given by a rule, in the parallel form used by [`Genome`].
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Tuning {
+    pub rule: Rule,
+    pub form: Form,
+    pub genome: Genome,
+}
+
+impl Tuning {
+    // Converts the standard form kp (1 + 1 / (ti s) + td s).
+    fn standard(rule: Rule, form: Form, kp: f64, ti: f64, td: f64) -> Self {
+        Self {
+            rule,
+            form,
+            genome: Genome::new(kp as f32, (kp / ti) as f32, (kp * td) as f32),
+        }
+    }
+
+    /// Rule and form, e.g. `AMIGO PID`.
+    pub fn name(&self) -> String {
+        format!("{} {}", self.rule.name(), self.form.name())
+    }
+}
+
+/// Ultimate gain and period, from the phase crossover of the frequency response.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UltimatePoint {
+    pub gain: f64,
+    pub period: f64,
+}
+
+impl UltimatePoint {
+    /// `None` when the plant's phase never reaches -180°.
+    pub fn of(model: Model) -> Option<Self> {
+        let margins = Margins::of(model, Genome::new(1.0, 0.0, 0.0));
+        let gain = 10f64.powf(margins.gain_margin_db? / 20.0);
+        let period = 2.0 * PI / margins.phase_crossover?;
+
+        (gain.is_finite() && gain > 0.0).then_some(Self { gain, period })
+    }
+}
+
+/// First-order plus dead-time approximation `K e^(-L s) / (T s + 1)`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fopdt {
+    pub gain: f64,
+    pub time_constant: f64,
+    pub dead_time: f64,
+}
+
+impl Fopdt {
+    /// Smith's two-point fit of the step response, `None` without a static gain.
+    pub fn fit(model: Model) -> Option<Self> {
+        let (numerator, denominator) = model.transfer_function();
+        let gain = *numerator.last()? as f64 / *denominator.last()? as f64;
+        if !(gain.is_finite() && gain > 0.0) {
+            return None;
+        }
+
+        let [t28, t63] = crossing_times(numerator, denominator, [0.283 * gain, 0.632 * gain])?;
+        let time_constant = 1.5 * (t63 - t28);
+        if time_constant <= 0.0 {
+            return None;
+        }
+        let dead_time = (t63 - time_constant).max(MIN_DEAD_TIME_RATIO * time_constant)
+            + model.dead_time() as f64;
+
+        Some(Self {
+            gain,
+            time_constant,
+            dead_time,
+        })
+    }
+}
+
+// First times the unit step response reaches each of the increasing `levels`.
+fn crossing_times(numerator: &[f32], denominator: &[f32], levels: [f64; 2]) -> Option<[f64; 2]> {
+    let lead = *denominator.first()? as f64;
+    let n = denominator.len() - 1;
+    if n == 0 || numerator.len() > denominator.len() {
+        return None;
+    }
+    let a: Vec<f64> = denominator.iter().map(|&c| c as f64 / lead).collect();
+    let mut b = vec![0.0; denominator.len() - numerator.len()];
+    b.extend(numerator.iter().map(|&c| c as f64 / lead));
+
+    // The state holds z and its derivatives, with denominator(s) z = u.
+    let feedthrough = b[0];
+    let output: Vec<f64> = (0..n).map(|i| b[n - i] - feedthrough * a[n - i]).collect();
+    let derivative = |x: &[f64]| -> Vec<f64> {
+        (0..n)
+            .map(|i| {
+                if i + 1 < n {
+                    x[i + 1]
+                } else {
+                    1.0 - (0..n).map(|j| a[n - j] * x[j]).sum::<f64>()
+                }
+            })
+            .collect()
+    };
+    let y = |x: &[f64]| feedthrough + output.iter().zip(x).map(|(c, x)| c * x).sum::<f64>();
+
+    // Residence time, -G'(0) / G(0), sets the time step.
+    let mut scale = (a[n - 1] / a[n]).abs();
+    if b[n] != 0.0 {
+        scale += (b[n - 1] / b[n]).abs();
+    }
+    let dt = if scale > 0.0 { scale } else { 1.0 } / STEPS_PER_TIME_SCALE;
+
+    let mut x = vec![0.0; n];
+    let mut previous = y(&x);
+    let mut times = [None; 2];
+    for step in 1..=MAX_STEPS {
+        let k1 = derivative(&x);
+        let shifted =
+            |k: &[f64], h: f64| -> Vec<f64> { x.iter().zip(k).map(|(x, k)| x + h * k).collect() };
+        let k2 = derivative(&shifted(&k1, dt / 2.0));
+        let k3 = derivative(&shifted(&k2, dt / 2.0));
+        let k4 = derivative(&shifted(&k3, dt));
+        for (i, x) in x.iter_mut().enumerate() {
+            *x += dt / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]);
+        }
+
+        let current = y(&x);
+        for (time, level) in times.iter_mut().zip(levels) {
+            if time.is_none() && current >= level {
+                let t = (level - previous) / (current - previous);
+                *time = Some((step as f64 - 1.0 + t) * dt);
+            }
+        }
+        if let [Some(first), Some(second)] = times {
+            return Some([first, second]);
+        }
+        previous = current;
+    }
+
+    None
+}
+
+/// Every rule that applies to `model`.
+pub fn tunings(model: Model) -> Vec<Tuning> {
+    let mut tunings = vec![];
+
+    if let Some(UltimatePoint {
+        gain: ku,
+        period: tu,
+    }) = UltimatePoint::of(model)
+    {
+        tunings.extend([
+            Tuning::standard(Rule::ZieglerNichols, Form::Pi, 0.45 * ku, tu / 1.2, 0.0),
+            Tuning::standard(
+                Rule::ZieglerNichols,
+                Form::Pid,
+                0.6 * ku,
+                tu / 2.0,
+                tu / 8.0,
+            ),
+        ]);
+    }
+
+    if let Some(Fopdt {
+        gain: k,
+        time_constant: t,
+        dead_time: l,
+    }) = Fopdt::fit(model)
+    {
+        let r = l / t;
+        tunings.extend([
+            Tuning::standard(
+                Rule::ZieglerNicholsStep,
+                Form::Pi,
+                0.9 * t / (k * l),
+                l / 0.3,
+                0.0,
+            ),
+            Tuning::standard(
+                Rule::ZieglerNicholsStep,
+                Form::Pid,
+                1.2 * t / (k * l),
+                2.0 * l,
+                0.5 * l,
+            ),
+            Tuning::standard(
+                Rule::CohenCoon,
+                Form::Pi,
+                t / (k * l) * (0.9 + r / 12.0),
+                l * (30.0 + 3.0 * r) / (9.0 + 20.0 * r),
+                0.0,
+            ),
+            Tuning::standard(
+                Rule::CohenCoon,
+                Form::Pid,
+                t / (k * l) * (4.0 / 3.0 + r / 4.0),
+                l * (32.0 + 6.0 * r) / (13.0 + 8.0 * r),
+                4.0 * l / (11.0 + 2.0 * r),
+            ),
+            // Closed-loop time constant equal to the dead time.
+            Tuning::standard(Rule::Simc, Form::Pi, t / (k * 2.0 * l), t.min(8.0 * l), 0.0),
+            Tuning::standard(
+                Rule::Amigo,
+                Form::Pi,
+                0.15 / k + (0.35 - l * t / (l + t).powi(2)) * t / (k * l),
+                0.35 * l + 13.0 * l * t * t / (t * t + 12.0 * l * t + 7.0 * l * l),
+                0.0,
+            ),
+            Tuning::standard(
+                Rule::Amigo,
+                Form::Pid,
+                (0.2 + 0.45 * t / l) / k,
+                (0.4 * l + 0.8 * t) / (l + 0.1 * t) * l,
+                0.5 * l * t / (0.3 * l + t),
+            ),
+        ]);
+    }
+
+    tunings
+}
+
+/// A classical tuning scored with the same fitness as the optimisers.
+#[derive(Clone)]
+pub struct Baseline {
+    pub tuning: Tuning,
+    pub individual: Individual,
+}
+
+/// Scores every rule in [`tunings`] on `objective`, best first.
+pub fn baselines(
+    objective: Arc<Objective>,
+    works: usize,
+    dir: Arc<str>,
+    seed: u64,
+    cache: FitnessCache,
+) -> Vec<Baseline> {
+    let tunings = tunings(objective.model);
+    let genomes = tunings.iter().map(|tuning| tuning.genome).collect();
+    let mut baselines: Vec<Baseline> =
+        Population::evaluate(genomes, works, objective, dir, seed, cache)
+            .into_iter()
+            .zip(tunings)
+            .map(|(individual, tuning)| Baseline { tuning, individual })
+            .collect();
+    baselines.sort_by(|a, b| a.individual.fitness().total_cmp(&b.individual.fitness()));
+
+    baselines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, what: &str) {
+        assert!(
+            (actual - expected).abs() <= 1e-2 * expected.abs(),
+            "{what}: {actual} instead of {expected}"
+        );
+    }
+
+    // Gains of `rule` and `form` on `model`, as `[kp, ki, kd]`.
+    fn gains(model: Model, rule: Rule, form: Form) -> [f64; 3] {
+        let tuning = tunings(model)
+            .into_iter()
+            .find(|tuning| tuning.rule == rule && tuning.form == form)
+            .unwrap_or_else(|| panic!("no {} {} tuning", rule.name(), form.name()));
+        let genome = tuning.genome;
+        [genome.kp as f64, genome.ki as f64, genome.kd as f64]
+    }
+
+    fn assert_standard(actual: [f64; 3], kp: f64, ti: f64, td: f64, what: &str) {
+        assert_close(actual[0], kp, what);
+        assert_close(actual[1], kp / ti, what);
+        if td == 0.0 {
+            assert_eq!(actual[2], 0.0, "{what}");
+        } else {
+            assert_close(actual[2], kp * td, what);
+        }
+    }
+
+    #[test]
+    fn ultimate_point_of_high_order_lag() {
+        // 1 / (s + 1)^4 crosses -180° at w = 1 with a gain of 1/4.
+        let point = UltimatePoint::of(Model::HighOrderLag).unwrap();
+        assert_close(point.gain, 4.0, "ultimate gain");
+        assert_close(point.period, 2.0 * PI, "ultimate period");
+        assert!(UltimatePoint::of(Model::DCMotor).is_none());
+    }
+
+    #[test]
+    fn ziegler_nichols_from_the_ultimate_point() {
+        let (ku, tu) = (4.0, 2.0 * PI);
+        let model = Model::HighOrderLag;
+        let pi = gains(model, Rule::ZieglerNichols, Form::Pi);
+        let pid = gains(model, Rule::ZieglerNichols, Form::Pid);
+        assert_standard(pi, 0.45 * ku, tu / 1.2, 0.0, "ZN PI");
+        assert_standard(pid, 0.6 * ku, tu / 2.0, tu / 8.0, "ZN PID");
+    }
+
+    #[test]
+    fn fopdt_fit_of_a_first_order_lag() {
+        // 1 / (s + 1) has no dead time, so the fit takes the smallest allowed.
+        let fit = Fopdt::fit(Model::DCMotor).unwrap();
+        assert_close(fit.gain, 1.0, "gain");
+        assert_close(fit.time_constant, 1.0, "time constant");
+        assert_close(fit.dead_time, MIN_DEAD_TIME_RATIO, "dead time");
+        assert!(Fopdt::fit(Model::Integrating).is_none());
+    }
+
+    #[test]
+    fn fopdt_rules_on_a_first_order_lag() {
+        // K = 1, T = 1 and L = 0.1 from the fit above.
+        let model = Model::DCMotor;
+        let cases = [
+            (Rule::ZieglerNicholsStep, Form::Pi, 9.0, 1.0 / 3.0, 0.0),
+            (Rule::ZieglerNicholsStep, Form::Pid, 12.0, 0.2, 0.05),
+            (Rule::CohenCoon, Form::Pi, 9.0833, 0.27545, 0.0),
+            (Rule::CohenCoon, Form::Pid, 13.5833, 0.23623, 0.035714),
+            (Rule::Simc, Form::Pi, 5.0, 0.8, 0.0),
+            (Rule::Amigo, Form::Pi, 2.8236, 0.60769, 0.0),
+            (Rule::Amigo, Form::Pid, 4.7, 0.42, 0.048544),
+        ];
+        for (rule, form, kp, ti, td) in cases {
+            let what = format!("{} {}", rule.name(), form.name());
+            assert_standard(gains(model, rule, form), kp, ti, td, &what);
+        }
+    }
+}
@@ -1,37 +1,166 @@
-use std::thread::JoinHandle;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+};
 
+/// Batch job run by [`work_serial`] and [`work_pool`].
 pub trait Work {
     type Input;
     type Output;
 
+    /// Processes one job, returning one output per input in order.
     fn work(&mut self, input: Vec<Self::Input>) -> Vec<Self::Output>;
-    fn set_id(&mut self, id: usize);
+    /// Called before each job with the batch index of its first input.
+    fn set_start(&mut self, start: usize);
 }
 
-pub fn work_pool<I, O, W>(size: usize, mut input: Vec<I>, work: W) -> Vec<O>
+/// Seed of the random stream owned by input `id`, whatever the job splitting.
+pub fn stream_seed(seed: u64, id: usize) -> u64 {
+    let mut z = seed ^ (id as u64).wrapping_add(1).wrapping_mul(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+// Splits `input` into about `workers` jobs, each with its first input index.
+fn into_jobs<I>(mut input: Vec<I>, workers: usize) -> VecDeque<(usize, Vec<I>)> {
+    let size = input.len().div_ceil(workers.max(1)).max(1);
+    let mut jobs = VecDeque::new();
+    let mut start = 0;
+    while !input.is_empty() {
+        let chunk = input.drain(..size.min(input.len())).collect::<Vec<_>>();
+        let len = chunk.len();
+        jobs.push_back((start, chunk));
+        start += len;
+    }
+
+    jobs
+}
+
+/// Runs `work` over `input` on the calling thread as a single job.
+pub fn work_serial<I, O, W>(input: Vec<I>, mut work: W) -> Vec<O>
+where
+    W: Work<Input = I, Output = O>,
+{
+    work.set_start(0);
+    work.work(input)
+}
+
+/// Runs `work` over `input` on `workers` threads, returning outputs in order.
+pub fn work_pool<I, O, W>(workers: usize, input: Vec<I>, work: W) -> Vec<O>
 where
     W: Work<Input = I, Output = O> + Send + 'static + Clone,
     I: Send + 'static,
     O: Send + 'static,
 {
+    let workers = workers.max(1);
+    let jobs = into_jobs(input, workers);
+    let total_jobs = jobs.len();
+    let queue = Arc::new(Mutex::new(jobs));
+
     let mut handles = vec![];
-    let mut id = 0;
-    while !input.is_empty() {
-        let chunk = input.drain(..size.min(input.len())).collect::<Vec<_>>();
+    for _ in 0..workers.min(total_jobs) {
+        let queue = queue.clone();
         let mut work = work.clone();
-        work.set_id(id);
-        let handle: JoinHandle<Vec<O>> = std::thread::spawn(move || work.work(chunk));
-        handles.push(handle);
+        let handle: JoinHandle<Vec<(usize, Vec<O>)>> = std::thread::spawn(move || {
+            let mut done = vec![];
+            loop {
+                let job = queue
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .pop_front();
+                let Some((start, chunk)) = job else {
+                    break;
+                };
 
-        id += 1;
+                work.set_start(start);
+                done.push((start, work.work(chunk)));
+            }
+
+            done
+        });
+        handles.push(handle);
     }
 
     let mut results = vec![];
     for handle in handles {
-        if let Ok(result) = handle.join() {
-            results.push(result);
+        match handle.join() {
+            Ok(done) => results.extend(done),
+            Err(panic) => std::panic::resume_unwind(panic),
         }
     }
+    results.sort_by_key(|(start, _)| *start);
 
-    results.into_iter().flatten().collect()
+    results.into_iter().flat_map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tags each input with its batch index and the seed of its stream.
+    #[derive(Clone)]
+    struct Tag {
+        seed: u64,
+        start: usize,
+    }
+
+    impl Work for Tag {
+        type Input = u32;
+        type Output = (u32, usize, u64);
+
+        fn work(&mut self, input: Vec<u32>) -> Vec<(u32, usize, u64)> {
+            input
+                .into_iter()
+                .enumerate()
+                .map(|(i, x)| (x, self.start + i, stream_seed(self.seed, self.start + i)))
+                .collect()
+        }
+
+        fn set_start(&mut self, start: usize) {
+            self.start = start;
+        }
+    }
+
+    #[test]
+    fn splits_into_jobs_with_their_start() {
+        let jobs: Vec<(usize, Vec<u32>)> = into_jobs((0..10).collect(), 3).into();
+        assert_eq!(
+            jobs,
+            [
+                (0, vec![0, 1, 2, 3]),
+                (4, vec![4, 5, 6, 7]),
+                (8, vec![8, 9])
+            ]
+        );
+        assert_eq!(into_jobs((0..3).collect::<Vec<u32>>(), 0).len(), 1);
+        assert_eq!(into_jobs((0..3).collect::<Vec<u32>>(), 8).len(), 3);
+        assert!(into_jobs(Vec::<u32>::new(), 4).is_empty());
+    }
+
+    #[test]
+    fn pool_matches_serial_for_any_worker_count() {
+        let tag = Tag { seed: 9, start: 0 };
+        let input: Vec<u32> = (100..137).collect();
+        let serial = work_serial(input.clone(), tag.clone());
+        assert_eq!(serial[5], (105, 5, stream_seed(9, 5)));
+        for workers in [0, 1, 2, 3, 8, 64] {
+            assert_eq!(
+                work_pool(workers, input.clone(), tag.clone()),
+                serial,
+                "{workers} workers"
+            );
+        }
+        assert!(work_pool(4, vec![], tag).is_empty());
+    }
+
+    #[test]
+    fn stream_seeds_are_distinct() {
+        let mut seeds: Vec<u64> = (0..1000).map(|id| stream_seed(1, id)).collect();
+        seeds.extend((0..1000).map(|id| stream_seed(2, id)));
+        seeds.sort_unstable();
+        seeds.dedup();
+        assert_eq!(seeds.len(), 2000);
+    }
 }
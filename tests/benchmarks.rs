@@ -0,0 +1,110 @@
+//! A short GA run on each benchmark plant must beat the classical tunings.
+
+use std::sync::Arc;
+
+use pid_opt::{
+    FitnessCache, GeneticAlgorithmBuilder, Model, Objective, Optimizer, Scenario, WeightedScenario,
+    tuning,
+};
+
+const POPULATION_SIZE: usize = 40;
+const GENERATIONS: usize = 15;
+const SEED: u64 = 0x2268a378740265f9;
+
+// Unit step response over long enough for the slowest plants to settle.
+fn objective(model: Model) -> Objective {
+    Objective {
+        model,
+        duration: 30.0,
+        scenarios: vec![WeightedScenario::new(
+            Scenario::Step { amplitude: 1.0 },
+            1.0,
+        )],
+        ..Objective::default()
+    }
+}
+
+// Checks that a short GA run beats every classical tuning.
+fn beats_classical_tunings(model: Model, [max_kp, max_ki, max_kd]: [f32; 3]) {
+    let baselines = tuning::baselines(
+        Arc::new(objective(model)),
+        0,
+        "benchmarks".into(),
+        SEED,
+        FitnessCache::default(),
+    );
+    let baseline = baselines
+        .first()
+        .unwrap_or_else(|| panic!("no classical tuning applies to {}", model.name()));
+
+    let mut ga = GeneticAlgorithmBuilder::default()
+        .with_objective(objective(model))
+        .with_population_size(POPULATION_SIZE)
+        .with_parallel_works(0)
+        .with_mutation_step(0.1)
+        .with_digit_range((-3, 0))
+        .with_max_kp(max_kp)
+        .with_max_ki(max_ki)
+        .with_max_kd(max_kd)
+        .with_output_dir("benchmarks")
+        .with_seed(SEED)
+        .build()
+        .unwrap();
+    for _ in 0..GENERATIONS {
+        ga.eval(0.75, 0.3).unwrap();
+    }
+    let best = ga.best().unwrap();
+
+    assert!(
+        best.fitness() < baseline.individual.fitness(),
+        "{}: GA fitness {} (kp {}, ki {}, kd {}) does not beat {} at {}",
+        model.name(),
+        best.fitness(),
+        best.kp(),
+        best.ki(),
+        best.kd(),
+        baseline.tuning.name(),
+        baseline.individual.fitness()
+    );
+}
+
+#[test]
+fn every_benchmark_has_a_classical_tuning() {
+    for model in Model::BENCHMARKS {
+        assert!(
+            !tuning::tunings(model).is_empty(),
+            "no classical tuning applies to {}",
+            model.name()
+        );
+    }
+}
+
+#[test]
+fn high_order_lag() {
+    beats_classical_tunings(Model::HighOrderLag, [5.0, 1.0, 5.0]);
+}
+
+#[test]
+fn multiple_lag() {
+    beats_classical_tunings(Model::MultipleLag, [10.0, 5.0, 5.0]);
+}
+
+#[test]
+fn non_minimum_phase() {
+    beats_classical_tunings(Model::NonMinimumPhase, [2.0, 1.0, 2.0]);
+}
+
+#[test]
+fn integrating() {
+    beats_classical_tunings(Model::Integrating, [5.0, 1.0, 5.0]);
+}
+
+#[test]
+fn oscillatory() {
+    beats_classical_tunings(Model::Oscillatory, [5.0, 2.0, 10.0]);
+}
+
+#[test]
+fn dead_time() {
+    beats_classical_tunings(Model::DeadTime, [1.5, 1.5, 0.5]);
+}
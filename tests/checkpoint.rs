@@ -0,0 +1,120 @@
+//! A GA resumed from a checkpoint must continue exactly as the saved run.
+
+use std::{env, fs};
+
+use pid_opt::{
+    FitnessCache, GeneticAlgorithm, GeneticAlgorithmBuilder, Memetic, Optimizer,
+    stats::GenerationStats,
+};
+
+const GENERATIONS: usize = 6;
+const SEED: u64 = 0x5d3c0a7e91b24f18;
+
+fn checkpoint_path(name: &str) -> String {
+    let dir = env::temp_dir().join("pid_opt_tests");
+    fs::create_dir_all(&dir).unwrap();
+    dir.join(format!("{name}-{}.json", std::process::id()))
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn builder(checkpoint: &str) -> GeneticAlgorithmBuilder {
+    GeneticAlgorithmBuilder::default()
+        .with_population_size(20)
+        .with_mutation_step(0.5)
+        .with_digit_range((-3, 1))
+        .with_max_kp(50.0)
+        .with_max_ki(50.0)
+        .with_max_kd(5.0)
+        .with_output_dir("checkpoint")
+        .with_seed(SEED)
+        .with_memetic(Memetic {
+            every: 2,
+            elites: 2,
+            budget: 10,
+            ..Memetic::default()
+        })
+        .with_checkpoint(checkpoint, 1)
+}
+
+// Everything but the cache counters and timing, which depend on the process.
+fn stats_bits(history: &[GenerationStats]) -> Vec<Vec<u64>> {
+    history
+        .iter()
+        .map(|stats| {
+            vec![
+                stats.generation as u64,
+                stats.size as u64,
+                stats.best.to_bits().into(),
+                stats.mean.to_bits().into(),
+                stats.median.to_bits().into(),
+                stats.worst.to_bits().into(),
+                stats.best_kp.to_bits().into(),
+                stats.best_ki.to_bits().into(),
+                stats.best_kd.to_bits().into(),
+                stats.diversity.to_bits().into(),
+                stats.filtered as u64,
+                stats.screened as u64,
+                stats.evaluations as u64,
+            ]
+        })
+        .collect()
+}
+
+fn population_bits(ga: &GeneticAlgorithm) -> Vec<[u32; 4]> {
+    ga.individuals()
+        .iter()
+        .map(|ind| {
+            let genome = ind.genome();
+            [
+                genome.kp.to_bits(),
+                genome.ki.to_bits(),
+                genome.kd.to_bits(),
+                ind.fitness().to_bits(),
+            ]
+        })
+        .collect()
+}
+
+#[test]
+fn resumed_run_matches_straight_run() {
+    let straight_path = checkpoint_path("straight");
+    let mut straight = builder(&straight_path).build().unwrap();
+    straight.run(GENERATIONS).unwrap();
+
+    let split_path = checkpoint_path("split");
+    let mut first = builder(&split_path).build().unwrap();
+    for _ in 0..GENERATIONS / 2 {
+        first.step().unwrap();
+    }
+    drop(first);
+    let mut resumed = GeneticAlgorithm::resume(&split_path, FitnessCache::default()).unwrap();
+    resumed.run(GENERATIONS).unwrap();
+
+    assert_eq!(resumed.generation(), straight.generation());
+    assert_eq!(population_bits(&resumed), population_bits(&straight));
+    assert_eq!(
+        stats_bits(resumed.statistics()),
+        stats_bits(straight.statistics())
+    );
+
+    fs::remove_file(straight_path).unwrap();
+    fs::remove_file(split_path).unwrap();
+}
+
+#[test]
+fn finished_run_is_not_polished_again() {
+    let path = checkpoint_path("finished");
+    let mut ga = builder(&path).build().unwrap();
+    ga.run(GENERATIONS).unwrap();
+    ga.save_checkpoint().unwrap();
+    let generation = ga.generation();
+
+    let mut resumed = GeneticAlgorithm::resume(&path, FitnessCache::default()).unwrap();
+    resumed.run(GENERATIONS).unwrap();
+
+    assert_eq!(resumed.generation(), generation);
+    assert_eq!(population_bits(&resumed), population_bits(&ga));
+
+    fs::remove_file(path).unwrap();
+}